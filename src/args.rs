@@ -1,4 +1,6 @@
-use clap::{Arg, ArgMatches, Command};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+
+use crate::listing::ListFormat;
 
 pub fn get_args() -> ArgMatches {
     Command::new(env!("CARGO_CRATE_NAME"))
@@ -7,33 +9,262 @@ pub fn get_args() -> ArgMatches {
             Arg::new("user_config")
                 .short('u')
                 .long("user-config")
-                .help("Path to user config file")
+                .help(
+                    "Path to user config file (.json, .toml, .yaml or .yml), defaults to \
+                     $GCOMA_CONFIG or $XDG_CONFIG_HOME/gcoma/config.json",
+                )
                 .value_name("USER_CONFIG")
-                .required(true),
+                .global(true),
         )
         .arg(
             Arg::new("list")
                 .short('l')
                 .long("list")
                 .num_args(0)
-                .conflicts_with_all(["connect", "remove"])
+                .conflicts_with_all(["connect", "remove", "import", "export_ssh_config"])
                 .help("List all sessions"),
         )
+        .arg(
+            format_arg()
+                .requires("list")
+                .help("Output format of --list, one row per session"),
+        )
         .arg(
             Arg::new("connect")
                 .short('c')
                 .long("connect")
-                .value_name("SESSION_INDEX")
-                .conflicts_with_all(["list", "remove"])
-                .help("Connect to session by index"),
+                .value_name("SESSION")
+                .conflicts_with_all(["list", "remove", "import", "export_ssh_config"])
+                .help("Connect to a session by path, name, fuzzy query or index"),
         )
         .arg(
             Arg::new("remove")
                 .short('r')
                 .long("remove")
-                .value_name("SESSION_GROUP_NAME")
-                .conflicts_with_all(["list", "connect"])
-                .help("Remove session group by name"),
+                .value_name("SESSION_GROUP_PATH")
+                .conflicts_with_all(["list", "connect", "import", "export_ssh_config"])
+                .help("Remove session group by name, nested groups as 'group/subgroup'"),
+        )
+        .arg(
+            Arg::new("import_ssh_config")
+                .long("import-ssh-config")
+                .value_name("SSH_CONFIG")
+                .num_args(0..=1)
+                .default_missing_value("~/.ssh/config")
+                .help("Import hosts from an OpenSSH client config file"),
+        )
+        .arg(
+            Arg::new("import_putty")
+                .long("import-putty")
+                .value_name("REG_OR_XML")
+                .help("Import sessions from a PuTTY .reg export or mtputty.xml"),
+        )
+        .group(
+            ArgGroup::new("import")
+                .args(["import_ssh_config", "import_putty"])
+                .conflicts_with_all(["list", "connect", "remove", "export_ssh_config"]),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .num_args(0)
+                .requires("import")
+                .help("Only print what would be imported"),
+        )
+        .arg(
+            Arg::new("export_ssh_config")
+                .long("export-ssh-config")
+                .value_name("PATH")
+                .conflicts_with_all(["list", "connect", "remove", "import"])
+                .help("Export ssh sessions as an OpenSSH client config file ('-' for stdout)"),
+        )
+        .arg(
+            Arg::new("add_session")
+                .long("add-session")
+                .value_names(["GROUP_PATH", "NAME", "ADDRESS"])
+                .num_args(3)
+                .conflicts_with_all(["list", "connect", "remove", "import", "export_ssh_config"])
+                .help("Add a session to a group, missing groups are created"),
+        )
+        .arg(
+            type_arg()
+                .requires("add_session")
+                .help("Connection type of the added session, defaults to the one of the group"),
+        )
+        .arg(
+            ssh_options_arg()
+                .requires("add_session")
+                .help("SSH options of the added session, e.g. \"-i ~/.ssh/key -J group/bastion\""),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List all sessions")
+                .arg(format_arg()),
+        )
+        .subcommand(
+            Command::new("connect")
+                .about("Connect to a session by 'group/session' path, name, fuzzy query or index")
+                .arg(
+                    Arg::new("query")
+                        .value_name("QUERY")
+                        .num_args(1..)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("add-group")
+                .about("Add a group, missing parent groups are created")
+                .arg(path_arg("GROUP_PATH"))
+                .arg(type_arg().help("Default connection type of the sessions in the group"))
+                .arg(user_arg().help("Default user of the sessions in the group"))
+                .arg(port_arg().help("Default port of the sessions in the group"))
+                .arg(ssh_options_arg().help("Default SSH options of the sessions in the group")),
+        )
+        .subcommand(
+            Command::new("add-session")
+                .about("Add a session to a group, missing groups are created")
+                .arg(path_arg("GROUP_PATH"))
+                .arg(Arg::new("name").value_name("NAME").required(true))
+                .arg(Arg::new("address").value_name("ADDRESS").required(true))
+                .arg(type_arg())
+                .arg(ssh_options_arg())
+                .arg(tags_arg()),
+        )
+        .subcommand(
+            Command::new("edit")
+                .about("Change a session or group, an empty value clears optional settings")
+                .arg(path_arg("PATH"))
+                .arg(
+                    Arg::new("name")
+                        .long("name")
+                        .value_name("NAME")
+                        .help("New name"),
+                )
+                .arg(type_arg().value_parser(["ssh", "telnet", ""]))
+                .arg(
+                    Arg::new("address")
+                        .long("address")
+                        .value_name("ADDRESS")
+                        .help("[username@]host[:port] of a session"),
+                )
+                .arg(ssh_options_arg())
+                .arg(tags_arg())
+                .arg(user_arg().help("Default user of a group"))
+                .arg(port_arg().help("Default port of a group")),
+        )
+        .subcommand(
+            Command::new("rm")
+                .about("Remove a session or a group with everything in it")
+                .arg(path_arg("PATH")),
+        )
+        .subcommand(
+            Command::new("mv")
+                .about("Move a session or group into another group ('/' for the top level)")
+                .arg(path_arg("PATH"))
+                .arg(path_arg("GROUP_PATH").id("dest")),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Import sessions from another tool")
+                .arg(
+                    Arg::new("source")
+                        .value_name("SOURCE")
+                        .value_parser(["ssh-config", "putty"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("path")
+                        .value_name("PATH")
+                        .help("File to import, defaults to ~/.ssh/config for ssh-config"),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .num_args(0)
+                        .help("Only print what would be imported"),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Export ssh sessions to another tool")
+                .arg(
+                    Arg::new("target")
+                        .value_name("TARGET")
+                        .value_parser(["ssh-config"])
+                        .required(true),
+                )
+                .arg(path_arg("PATH").help("File to write, '-' for stdout")),
+        )
+        .subcommand(
+            Command::new("convert")
+                .about("Write the config to a new file in the format of its extension")
+                .arg(
+                    path_arg("PATH").help("File to create, ending in .json, .toml, .yaml or .yml"),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Show the config file in use and which ssh and telnet clients are found"),
+        )
+        .subcommand(
+            Command::new("restore-backup")
+                .about("Replace the config with one of its backups, the newest by default")
+                .arg(
+                    Arg::new("backup")
+                        .value_name("BACKUP")
+                        .help("Number of the backup in --list or its file name"),
+                )
+                .arg(
+                    Arg::new("list")
+                        .short('l')
+                        .long("list")
+                        .num_args(0)
+                        .conflicts_with("backup")
+                        .help("Only list the backups, newest first"),
+                ),
         )
         .get_matches()
 }
+
+fn path_arg(value_name: &'static str) -> Arg {
+    Arg::new("path").value_name(value_name).required(true)
+}
+
+fn format_arg() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .value_name("FORMAT")
+        .value_parser(ListFormat::NAMES)
+        .help("Output format, one row per session")
+}
+
+fn type_arg() -> Arg {
+    Arg::new("session_type")
+        .long("type")
+        .value_name("TYPE")
+        .value_parser(["ssh", "telnet"])
+        .help("Connection type, defaults to the one of the group")
+}
+
+fn user_arg() -> Arg {
+    Arg::new("user").long("user").value_name("USER")
+}
+
+fn port_arg() -> Arg {
+    Arg::new("port").long("port").value_name("PORT")
+}
+
+fn ssh_options_arg() -> Arg {
+    Arg::new("ssh_options")
+        .long("ssh-options")
+        .value_name("OPTIONS")
+        .allow_hyphen_values(true)
+        .help("SSH options, e.g. \"-i ~/.ssh/key -J group/bastion\"")
+}
+
+fn tags_arg() -> Arg {
+    Arg::new("tags")
+        .long("tags")
+        .value_name("TAGS")
+        .help("Comma separated tags")
+}
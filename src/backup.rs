@@ -0,0 +1,147 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// number of backups kept next to the config, older ones are deleted
+pub const KEEP: usize = 10;
+
+const EXTENSION: &str = ".bak";
+
+/// `yyyymmdd-hhmmss-mmm` in UTC
+pub fn timestamp(millis: u64) -> String {
+    let secs = millis / 1000;
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        millis % 1000
+    )
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn sibling(path: &Path, name: String) -> PathBuf {
+    path.with_file_name(name)
+}
+
+/// writes `contents` to a temporary file next to `path`, syncs it and
+/// renames it over `path`, so `path` is either the old or the new file
+///
+/// a symlink is followed so the file it points to is replaced rather than the
+/// link, and the new file keeps the permissions of the old one
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let permissions = fs::metadata(path).map(|m| m.permissions()).ok();
+    let tmp = sibling(path, format!(".{}.tmp", file_name(path)));
+
+    let written = File::create(&tmp).and_then(|mut f| {
+        if let Some(permissions) = permissions {
+            f.set_permissions(permissions)?;
+        }
+        f.write_all(contents.as_bytes())?;
+        f.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    // the rename is only durable once the directory is synced
+    #[cfg(target_family = "unix")]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|d| d.sync_all());
+    }
+
+    Ok(())
+}
+
+/// backups of the file at `path`, newest first
+pub fn list(path: &Path) -> Vec<PathBuf> {
+    let prefix = format!("{}.", file_name(path));
+    let dir = match path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        Some(dir) => dir.to_path_buf(),
+        None => PathBuf::from("."),
+    };
+
+    let mut backups: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|name| {
+                name.strip_prefix(&prefix)
+                    .and_then(|rest| rest.strip_suffix(EXTENSION))
+                    .is_some_and(|stamp| stamp.len() == 19 && stamp.as_bytes()[8] == b'-')
+            })
+            .map(|name| sibling(path, name))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    // the timestamps sort like the names
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+/// copies the file at `path` to a timestamped backup next to it and deletes
+/// all but the `KEEP` newest backups, returns the backup if there was a file
+pub fn create(path: &Path) -> io::Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let mut millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let mut backup;
+
+    // never overwrite a backup, two saves can happen in the same millisecond
+    loop {
+        backup = sibling(
+            path,
+            format!("{}.{}{}", file_name(path), timestamp(millis), EXTENSION),
+        );
+        if !backup.exists() {
+            break;
+        }
+        millis += 1;
+    }
+
+    fs::copy(path, &backup)?;
+    rotate(path, KEEP)?;
+
+    Ok(Some(backup))
+}
+
+/// deletes all but the `keep` newest backups of `path`
+pub fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    for old in list(path).into_iter().skip(keep) {
+        fs::remove_file(old)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,379 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use clap::ArgMatches;
+
+use crate::backup;
+use crate::config_format::ConfigFormat;
+use crate::error::{Error, Result};
+use crate::interop;
+use crate::listing::{self, ListFormat};
+use crate::paths;
+use crate::reqs_check;
+use crate::session_core::{
+    address, connection_type::ConnectionType, session::Session, session_defaults::SessionDefaults,
+    ssh_options::SshOptions,
+};
+use crate::ui::{
+    config::{Config, NodeId},
+    edit_state::EditState,
+};
+
+fn connection_type(m: &ArgMatches) -> Option<ConnectionType> {
+    match m.get_one::<String>("session_type")?.as_str() {
+        "telnet" => Some(ConnectionType::Telnet),
+        _ => Some(ConnectionType::SSH),
+    }
+}
+
+fn ssh_options(m: &ArgMatches) -> Result<SshOptions> {
+    match m.get_one::<String>("ssh_options") {
+        Some(opts) => SshOptions::parse(opts).map_err(Error::Invalid),
+        None => Ok(SshOptions::default()),
+    }
+}
+
+pub fn list(cfg: &Config, format: Option<ListFormat>) -> Result<()> {
+    if let Some(format) = format {
+        print!("{}", listing::render(cfg, format).map_err(io::Error::from)?);
+        return Ok(());
+    }
+
+    let mut i = 0;
+
+    for row in cfg.rows().iter() {
+        let indent = "  ".repeat(row.depth);
+
+        match &row.id {
+            NodeId::Group(idx) => {
+                let name = cfg.group(idx).map_or("", |sg| &sg.name);
+                println!("{}{}:", indent, name);
+            }
+            id => {
+                if let Some(s) = cfg.effective_session(id) {
+                    println!("{}{}. {} ({})", indent, i, s.name, s.address());
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn connect(cfg: &Config, query: &str) -> Result<()> {
+    let (path, s) = cfg.query_session(query).map_err(Error::Invalid)?;
+    let jump_chain = cfg.resolve_jump_chain(&s).map_err(Error::Invalid)?;
+
+    println!("Connecting to {} ({})", path, s.address());
+    s.connect(&jump_chain)
+}
+
+/// `m` holds the `--user`, `--port`, `--type` and `--ssh-options` defaults
+pub fn add_group(cfg_path: &str, mut cfg: Config, path: &str, m: &ArgMatches) -> Result<()> {
+    let port = match m.get_one::<String>("port") {
+        Some(p) => Some(address::parse_port(p).map_err(|e| Error::Invalid(e.to_string()))?),
+        None => None,
+    };
+    let defaults = SessionDefaults {
+        user: m.get_one::<String>("user").cloned(),
+        port,
+        connection_type: connection_type(m),
+        ssh: ssh_options(m)?,
+    };
+
+    cfg.add_group(path, defaults).map_err(Error::Invalid)?;
+    cfg.save(cfg_path)?;
+
+    println!("Added {}", path);
+    Ok(())
+}
+
+/// `m` holds the `--type`, `--ssh-options` and `--tags` of the session
+pub fn add_session(
+    cfg_path: &str,
+    mut cfg: Config,
+    group_path: &str,
+    name: &str,
+    address: &str,
+    tags: Option<&String>,
+    m: &ArgMatches,
+) -> Result<()> {
+    let mut sb = Session::builder();
+    sb.name(name.to_string())
+        .address(address)
+        .map_err(|e| Error::Invalid(format!("{}: {}", address, e)))?;
+
+    if let Some(t) = connection_type(m) {
+        sb.connection_type(t);
+    }
+    if m.get_one::<String>("ssh_options").is_some() {
+        sb.ssh_options(ssh_options(m)?);
+    }
+    if let Some(tags) = tags {
+        sb.tags(Session::parse_tags(tags));
+    }
+
+    cfg.add_session(group_path, sb.build())
+        .map_err(Error::Invalid)?;
+    cfg.save(cfg_path)?;
+
+    println!("Added {}/{}", group_path, name);
+    Ok(())
+}
+
+/// settings `edit` can change, as (argument id, field of the edit form)
+const EDIT_SETTINGS: [(&str, &str); 7] = [
+    ("name", "name"),
+    ("session_type", "type"),
+    ("address", "address"),
+    ("ssh_options", "ssh-options"),
+    ("tags", "tags"),
+    ("user", "user"),
+    ("port", "port"),
+];
+
+/// changes the settings given in `m` the same way the edit form of the TUI does
+pub fn edit(cfg_path: &str, mut cfg: Config, path: &str, m: &ArgMatches) -> Result<()> {
+    let id = cfg.find_node(path).map_err(Error::Invalid)?;
+    let mut form = EditState::new(&cfg, id).ok_or_else(|| Error::Invalid(path.to_string()))?;
+    let mut changed = false;
+
+    for (arg, field) in EDIT_SETTINGS.iter() {
+        if let Some(value) = m.get_one::<String>(arg) {
+            if !form.set(field, value) {
+                return Err(Error::Invalid(format!(
+                    "--{} can't be set on '{}'",
+                    field, path
+                )));
+            }
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return Err(Error::Invalid("nothing to change".to_string()));
+    }
+
+    form.apply(&mut cfg).map_err(Error::Invalid)?;
+    cfg.save(cfg_path)?;
+
+    println!("Changed {}", path);
+    Ok(())
+}
+
+/// removes the session or group at `path`
+pub fn remove(cfg_path: &str, mut cfg: Config, path: &str) -> Result<()> {
+    let id = cfg.find_node(path).map_err(Error::Invalid)?;
+
+    cfg.remove(&id);
+    cfg.save(cfg_path)?;
+
+    println!("Removed {}", path);
+    Ok(())
+}
+
+pub fn move_node(cfg_path: &str, mut cfg: Config, path: &str, group_path: &str) -> Result<()> {
+    let new_path = cfg.move_node(path, group_path).map_err(Error::Invalid)?;
+    cfg.save(cfg_path)?;
+
+    println!("Moved {} to {}", path, new_path);
+    Ok(())
+}
+
+pub enum ImportSource<'a> {
+    SshConfig(&'a str),
+    Putty(&'a str),
+}
+
+pub fn import(cfg_path: &str, mut cfg: Config, source: ImportSource, dry_run: bool) -> Result<()> {
+    let groups = match source {
+        ImportSource::SshConfig(p) => {
+            interop::ssh_config::import(&paths::expand_tilde(p)).map_err(|e| Error::file(p, e))?
+        }
+        ImportSource::Putty(p) => {
+            interop::putty::import(&paths::expand_tilde(p)).map_err(|e| Error::file(p, e))?
+        }
+    };
+
+    let added = if dry_run {
+        cfg.dedup_groups(groups)
+    } else {
+        cfg.merge_groups(groups)
+    };
+
+    if added.is_empty() {
+        println!("Nothing to import");
+    } else {
+        for line in interop::preview_lines(&added) {
+            println!("{}", line);
+        }
+    }
+
+    if !dry_run && !added.is_empty() {
+        cfg.save(cfg_path)?;
+    }
+
+    Ok(())
+}
+
+/// writes the ssh sessions as an OpenSSH client config to `path`, `-` is stdout
+pub fn export_ssh_config(cfg: &Config, path: &str) -> Result<()> {
+    let ssh_cfg = interop::ssh_config::export(cfg);
+
+    if path == "-" {
+        print!("{}", ssh_cfg);
+    } else {
+        fs::write(paths::expand_tilde(path), ssh_cfg).map_err(|e| Error::file(path, e))?;
+    }
+
+    Ok(())
+}
+
+/// writes `cfg` to the new file `path` in the format of its extension, fails
+/// without writing anything if the config doesn't read back the same
+pub fn convert(cfg_path: &str, cfg: &Config, path: &str) -> Result<()> {
+    let target = paths::expand_tilde(path);
+
+    if ConfigFormat::from_extension(&target).is_none() {
+        return Err(Error::Invalid(format!(
+            "can't tell the format of '{}', use one of .{}",
+            path,
+            ConfigFormat::EXTENSIONS.join(", .")
+        )));
+    }
+    if target.exists() {
+        return Err(Error::Invalid(format!(
+            "'{}' already exists, convert never overwrites a file",
+            path
+        )));
+    }
+
+    let target = target.to_string_lossy();
+    cfg.save(&target)?;
+
+    match Config::load(&target) {
+        Ok(converted) if converted.session_groups == cfg.session_groups => {}
+        loaded => {
+            let _ = fs::remove_file(target.as_ref());
+            return Err(match loaded {
+                Err(e) => e,
+                Ok(_) => Error::Invalid(format!(
+                    "'{}' doesn't read back the same as {}",
+                    path, cfg_path
+                )),
+            });
+        }
+    }
+
+    println!("Converted {} to {}", cfg_path, path);
+    Ok(())
+}
+
+fn plural(n: usize, word: &str) -> String {
+    match n {
+        1 => format!("{} {}", n, word),
+        _ => format!("{} {}s", n, word),
+    }
+}
+
+/// reports the config in use and which connection types can be used, fails if
+/// the config is broken or a session needs a client that isn't installed,
+/// clients are searched in the directories of `search_path`
+pub fn doctor(cfg_path: &str, cfg: Result<Config>, search_path: &str) -> Result<()> {
+    let mut problem = None;
+
+    let sessions = match cfg {
+        Ok(cfg) => {
+            let sessions = cfg.sessions();
+            println!(
+                "{:<8}{}, {}",
+                "config",
+                cfg_path,
+                plural(sessions.len(), "session")
+            );
+            Some(sessions)
+        }
+        Err(e) => {
+            println!("{:<8}{}", "config", e);
+            problem = Some(e);
+            None
+        }
+    };
+
+    for connection_type in ConnectionType::ALL.iter() {
+        let program = connection_type.to_string();
+        let found = reqs_check::find_in_path(&program, search_path);
+
+        let mut line = match &found {
+            Some(path) => format!("{:<8}{}", program, path.display()),
+            None => format!("{:<8}not found", program),
+        };
+
+        if let Some(sessions) = &sessions {
+            let used = sessions
+                .iter()
+                .filter(|(_, s)| s.connection_type() == *connection_type)
+                .count();
+            line.push_str(&format!(", used by {}", plural(used, "session")));
+
+            if found.is_none() && used > 0 && problem.is_none() {
+                problem = Some(Error::MissingProgram(program));
+            }
+        }
+
+        println!("{}", line);
+    }
+
+    match problem {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// replaces the config with the backup numbered `backup` in the listing
+/// (or named `backup`), the newest one if `None`, the replaced config is
+/// backed up as well so this can be undone
+pub fn restore_backup(cfg_path: &str, backup: Option<&str>, list: bool) -> Result<()> {
+    let backups = backup::list(Path::new(cfg_path));
+
+    if list {
+        for (i, b) in backups.iter().enumerate() {
+            println!("{}. {}", i + 1, b.display());
+        }
+        return Ok(());
+    }
+
+    let chosen = match backup {
+        None => backups.first(),
+        Some(b) => match b.parse::<usize>() {
+            Ok(i) => i.checked_sub(1).and_then(|i| backups.get(i)),
+            Err(_) => backups
+                .iter()
+                .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy() == b)),
+        },
+    };
+    let chosen = match chosen {
+        Some(c) => c.to_string_lossy().into_owned(),
+        None => {
+            return Err(Error::Invalid(format!(
+                "there is no backup {}of {}, see restore-backup --list",
+                backup.map(|b| format!("'{}' ", b)).unwrap_or_default(),
+                cfg_path
+            )))
+        }
+    };
+
+    // a backup that isn't a valid config is never restored, backups keep the
+    // format of the config but not its extension. It is restored as it is,
+    // with its comments
+    Config::load_as(&chosen, ConfigFormat::of(Path::new(cfg_path)))?;
+    let contents = fs::read_to_string(&chosen).map_err(|e| Error::file(&chosen, e))?;
+    let path = Path::new(cfg_path);
+    backup::create(path).map_err(|e| Error::file(path, e))?;
+    backup::write_atomic(path, &contents).map_err(|e| Error::file(path, e))?;
+
+    println!("Restored {}", chosen);
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::error;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+use toml_edit::{ArrayOfTables, DocumentMut, InlineTable, Item, Table};
+
+/// File formats a config can be stored in, chosen by the extension of the file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+pub type FormatError = Box<dyn error::Error + Send + Sync>;
+
+impl ConfigFormat {
+    pub const EXTENSIONS: [&'static str; 4] = ["json", "toml", "yaml", "yml"];
+
+    /// format of the file at `path`, `None` if the extension isn't one of
+    /// `EXTENSIONS`
+    pub fn from_extension(path: &Path) -> Option<ConfigFormat> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+
+        match ext.as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "toml" => Some(ConfigFormat::Toml),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// like `from_extension`, files with any other extension are json
+    pub fn of(path: &Path) -> ConfigFormat {
+        ConfigFormat::from_extension(path).unwrap_or(ConfigFormat::Json)
+    }
+
+    /// parses `s` into a json value, so older layouts can be migrated the same
+    /// way in every format
+    pub fn parse(self, s: &str) -> Result<Value, FormatError> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::from_str(s)?,
+            ConfigFormat::Toml => toml::from_str(s)?,
+            ConfigFormat::Yaml => serde_yaml::from_value(untag(serde_yaml::from_str(s)?))?,
+        })
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String, FormatError> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(value)?,
+            ConfigFormat::Toml => toml::to_string_pretty(value)?,
+            ConfigFormat::Yaml => serde_yaml::to_string(&untag(serde_yaml::to_value(value)?))?,
+        })
+    }
+
+    /// like `serialize`, keeping what it can of `current`, the file that is
+    /// replaced: the comments and formatting of a toml file stay with the
+    /// keys and tables that are still there
+    pub fn serialize_over<T: Serialize>(
+        self,
+        value: &T,
+        current: Option<&str>,
+    ) -> Result<String, FormatError> {
+        let new = self.serialize(value)?;
+
+        // a file that doesn't parse has nothing worth keeping
+        let mut doc = match (self, current.map(str::parse::<DocumentMut>)) {
+            (ConfigFormat::Toml, Some(Ok(doc))) => doc,
+            _ => return Ok(new),
+        };
+
+        update_table(doc.as_table_mut(), new.parse::<DocumentMut>()?.into_table());
+        renumber(doc.as_table_mut(), &mut 0);
+        Ok(doc.to_string())
+    }
+}
+
+/// true if the yaml in `s` has a comment, which is lost when it is written
+/// again, text that only looks like a comment counts as well
+pub fn has_yaml_comments(s: &str) -> bool {
+    s.lines().any(|line| {
+        let mut quote = None;
+        let mut prev = ' ';
+
+        for c in line.chars() {
+            match quote {
+                Some(q) if c == q && prev != '\\' => quote = None,
+                Some(_) => {}
+                None if c == '#' && prev.is_whitespace() => return true,
+                None if (c == '"' || c == '\'')
+                    && (prev.is_whitespace() || "[{:,-".contains(prev)) =>
+                {
+                    quote = Some(c)
+                }
+                None => {}
+            }
+            prev = c;
+        }
+
+        false
+    })
+}
+
+/// copies `new` into `old`, the keys that are in both keep their comments
+/// and formatting
+fn update_table(old: &mut Table, new: Table) {
+    old.retain(|key, _| new.contains_key(key));
+
+    for (key, item) in new {
+        match old.get_mut(&key) {
+            // a value that became a table, or the other way around, is
+            // written like a new key
+            Some(o) if o.is_value() == item.is_value() => update_item(o, item),
+            _ => {
+                old.insert(&key, item);
+            }
+        }
+    }
+}
+
+fn update_item(old: &mut Item, new: Item) {
+    match (old, new) {
+        (Item::Table(o), Item::Table(n)) => update_table(o, n),
+        (Item::ArrayOfTables(o), Item::ArrayOfTables(n)) => update_tables(o, n),
+        (Item::Value(o), Item::Value(n)) => update_value(o, n),
+        (old, new) => *old = new,
+    }
+}
+
+/// groups and sessions are matched by name, so they keep their comments
+/// when they are moved, anything without a name by its position
+fn update_tables(old: &mut ArrayOfTables, new: ArrayOfTables) {
+    let mut unused: Vec<Option<Table>> = old.iter().cloned().map(Some).collect();
+    let mut updated = ArrayOfTables::new();
+
+    for (i, table) in new.into_iter().enumerate() {
+        let name = table.get("name").and_then(Item::as_str).map(str::to_string);
+        let found = match name {
+            Some(name) => unused.iter().position(|o| {
+                o.as_ref()
+                    .and_then(|o| o.get("name"))
+                    .and_then(Item::as_str)
+                    == Some(name.as_str())
+            }),
+            None => Some(i),
+        };
+
+        match found.and_then(|i| unused.get_mut(i)?.take()) {
+            Some(mut o) => {
+                update_table(&mut o, table);
+                updated.push(o);
+            }
+            None => updated.push(table),
+        }
+    }
+
+    *old = updated;
+}
+
+fn update_inline_table(old: &mut InlineTable, new: InlineTable) {
+    old.retain(|key, _| new.contains_key(key));
+
+    for (key, value) in new {
+        match old.get_mut(&key) {
+            Some(o) => update_value(o, value),
+            None => {
+                old.insert(&key, value);
+            }
+        }
+    }
+}
+
+fn update_value(old: &mut toml_edit::Value, new: toml_edit::Value) {
+    use toml_edit::Value as Toml;
+
+    match (old, new) {
+        (Toml::Array(o), Toml::Array(n)) if o.len() == n.len() => {
+            for (o, n) in o.iter_mut().zip(n) {
+                update_value(o, n);
+            }
+        }
+        (Toml::InlineTable(o), Toml::InlineTable(n)) => update_inline_table(o, n),
+        (old, new) => {
+            let decor = old.decor().clone();
+            *old = new;
+            *old.decor_mut() = decor;
+        }
+    }
+}
+
+/// tables are written in the order of their position, old and new ones are
+/// numbered again in the order they are in now
+fn renumber(table: &mut Table, position: &mut usize) {
+    if !table.is_dotted() {
+        table.set_position(*position);
+        *position += 1;
+    }
+
+    for (_, item) in table.iter_mut() {
+        match item {
+            Item::Table(t) => renumber(t, position),
+            Item::ArrayOfTables(tables) => {
+                for t in tables.iter_mut() {
+                    renumber(t, position);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// replaces yaml tags like `!session a/b` (how enums are written by default)
+/// with `session: a/b`, the way they are written in json and toml
+fn untag(value: serde_yaml::Value) -> serde_yaml::Value {
+    use serde_yaml::Value as Yaml;
+
+    match value {
+        Yaml::Tagged(tagged) => {
+            let mut map = serde_yaml::Mapping::new();
+            map.insert(
+                Yaml::String(tagged.tag.to_string().trim_start_matches('!').to_string()),
+                untag(tagged.value),
+            );
+            Yaml::Mapping(map)
+        }
+        Yaml::Sequence(seq) => Yaml::Sequence(seq.into_iter().map(untag).collect()),
+        Yaml::Mapping(map) => Yaml::Mapping(map.into_iter().map(|(k, v)| (k, untag(v))).collect()),
+        other => other,
+    }
+}
@@ -0,0 +1,76 @@
+use std::{fmt, io};
+
+use crate::config_format::FormatError;
+use crate::migrations::MigrationError;
+
+/// Everything that can go wrong in gcoma, shown to the user as a message on
+/// stderr or in the status bar of the TUI
+#[derive(Debug)]
+pub enum Error {
+    /// a file couldn't be read or written
+    File { path: String, source: io::Error },
+    /// the config file isn't a valid config
+    Parse { path: String, source: FormatError },
+    /// the config file is of a layout that can't be upgraded to the current one
+    Migration {
+        path: String,
+        source: MigrationError,
+    },
+    /// a value given by the user was rejected
+    Invalid(String),
+    /// `ssh` or `telnet` isn't in PATH
+    MissingProgram(String),
+    /// `ssh` or `telnet` couldn't be started
+    Spawn { program: String, source: io::Error },
+    /// anything else, mostly the terminal
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn file(path: impl AsRef<std::path::Path>, source: io::Error) -> Error {
+        Error::File {
+            path: path.as_ref().display().to_string(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::File { path, source } => write!(f, "{}: {}", path, source),
+            Error::Parse { path, source } => {
+                write!(f, "{} is not a valid config: {}", path, source)
+            }
+            Error::Migration { path, source } => write!(f, "{}: {}", path, source),
+            Error::Invalid(msg) => write!(f, "{}", msg),
+            Error::MissingProgram(program) => {
+                write!(f, "{} not found, install it or add it to PATH", program)
+            }
+            Error::Spawn { program, source } => {
+                write!(f, "can't start '{}': {}", program, source)
+            }
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::File { source, .. } | Error::Spawn { source, .. } => Some(source),
+            Error::Parse { source, .. } => Some(source.as_ref()),
+            Error::Migration { source, .. } => Some(source),
+            Error::Io(e) => Some(e),
+            Error::Invalid(_) | Error::MissingProgram(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,62 @@
+use crate::session_core::session::Session;
+
+const MATCH: i64 = 1;
+const CONSECUTIVE: i64 = 5;
+const WORD_START: i64 = 3;
+const SUBSTRING: i64 = 10;
+const MAX_GAP_PENALTY: usize = 3;
+
+/// scores `pattern` as a case insensitive subsequence of `text`, `None` if
+/// it doesn't match, higher is better
+pub fn score(pattern: &str, text: &str) -> Option<i64> {
+    let pattern = pattern.to_lowercase();
+    let text_lc = text.to_lowercase();
+    let text: Vec<char> = text_lc.chars().collect();
+
+    let mut score = 0;
+    let mut start = 0;
+    let mut prev: Option<usize> = None;
+
+    for pc in pattern.chars() {
+        let j = start + text[start..].iter().position(|c| *c == pc)?;
+
+        score += MATCH;
+        if j == 0 || !text[j - 1].is_alphanumeric() {
+            score += WORD_START;
+        }
+        match prev {
+            Some(p) if p + 1 == j => score += CONSECUTIVE,
+            Some(p) => score -= (j - p - 1).min(MAX_GAP_PENALTY) as i64,
+            None => {}
+        }
+
+        prev = Some(j);
+        start = j + 1;
+    }
+
+    if text_lc.contains(&pattern) {
+        score += SUBSTRING;
+    }
+
+    Some(score)
+}
+
+/// every whitespace separated term of `query` has to match one of `fields`,
+/// the best field of each term counts
+pub fn score_fields(query: &str, fields: &[&str]) -> Option<i64> {
+    let mut total = 0;
+
+    for term in query.split_whitespace() {
+        total += fields.iter().filter_map(|f| score(term, f)).max()?;
+    }
+
+    Some(total)
+}
+
+/// matches `query` against the `group/.../session` path, user, host and tags
+pub fn score_session(query: &str, path: &str, session: &Session) -> Option<i64> {
+    let mut fields = vec![path, session.get_user_name(), session.host.as_str()];
+    fields.extend(session.tags.iter().map(|t| t.as_str()));
+
+    score_fields(query, &fields)
+}
@@ -0,0 +1,34 @@
+pub mod putty;
+pub mod ssh_config;
+
+use crate::session_core::session_group::SessionGroup;
+
+/// human readable summary of the sessions that an import would add
+pub fn preview_lines(groups: &[SessionGroup]) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for sg in groups.iter() {
+        push_preview_lines(sg, 0, &mut lines);
+    }
+
+    lines
+}
+
+fn push_preview_lines(sg: &SessionGroup, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    lines.push(format!("{}{}:", indent, sg.name));
+
+    for s in sg.sessions.iter() {
+        lines.push(format!(
+            "{}  + {} ({} {})",
+            indent,
+            s.name,
+            s.connection_type(),
+            s.address()
+        ));
+    }
+
+    for g in sg.groups.iter() {
+        push_preview_lines(g, depth + 1, lines);
+    }
+}
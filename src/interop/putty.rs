@@ -0,0 +1,264 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::session_core::{
+    address::Address, connection_type::ConnectionType, session::Session,
+    session_group::SessionGroup,
+};
+
+const SESSIONS_KEY: &str = "\\Software\\SimonTatham\\PuTTY\\Sessions\\";
+
+/// Name of the group for PuTTY sessions and MTPuTTY servers outside of folders
+pub const PUTTY_GROUP_NAME: &str = "PuTTY";
+pub const MTPUTTY_GROUP_NAME: &str = "MTPuTTY";
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// regedit writes UTF-16LE with a BOM, older exports are plain text
+fn decode_reg(bytes: &[u8]) -> io::Result<String> {
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+
+        return String::from_utf16(&units).map_err(|e| invalid_data(e.to_string()));
+    }
+
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    String::from_utf8(bytes.to_vec()).map_err(|e| invalid_data(e.to_string()))
+}
+
+/// PuTTY stores session names with `%XX` escapes
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// unescapes a quoted .reg string value
+fn reg_string(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    Some(inner.replace("\\\"", "\"").replace("\\\\", "\\"))
+}
+
+#[derive(Default)]
+struct PuttySession {
+    name: String,
+    host_name: String,
+    user_name: String,
+    port: u32,
+    protocol: String,
+}
+
+impl PuttySession {
+    fn to_session(&self) -> Option<Session> {
+        let connection_type = match self.protocol.as_str() {
+            "ssh" | "" => ConnectionType::SSH,
+            "telnet" => ConnectionType::Telnet,
+            // raw, rlogin and serial can't be represented
+            _ => return None,
+        };
+
+        // `HostName` may carry the user as `user@host`
+        let addr = Address::parse(&self.host_name).ok()?;
+        let user = match self.user_name.as_str() {
+            "" => addr.user,
+            u => Some(u.to_string()),
+        };
+        let port = match u16::try_from(self.port) {
+            Ok(p) if p != 0 => p,
+            _ => connection_type.default_port(),
+        };
+
+        Some(Session::new(
+            self.name.clone(),
+            user,
+            addr.host,
+            port,
+            connection_type,
+        ))
+    }
+}
+
+/// parses a registry export of `HKCU\Software\SimonTatham\PuTTY\Sessions`
+pub fn parse_reg(content: &str) -> Vec<Session> {
+    let mut sessions: Vec<PuttySession> = Vec::new();
+    let mut in_session = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_session = match key.find(SESSIONS_KEY) {
+                Some(i) => {
+                    let name = &key[i + SESSIONS_KEY.len()..];
+
+                    // sub keys like `...\Sessions\foo\bar` are not sessions
+                    if !name.is_empty() && !name.contains('\\') {
+                        sessions.push(PuttySession {
+                            name: percent_decode(name),
+                            ..PuttySession::default()
+                        });
+                        true
+                    } else {
+                        false
+                    }
+                }
+                None => false,
+            };
+            continue;
+        }
+
+        if !in_session {
+            continue;
+        }
+
+        let (name, value) = match line.split_once('=') {
+            Some((n, v)) => (reg_string(n), v),
+            None => continue,
+        };
+        let session = sessions.last_mut().unwrap();
+
+        match name.as_deref() {
+            Some("HostName") => session.host_name = reg_string(value).unwrap_or_default(),
+            Some("UserName") => session.user_name = reg_string(value).unwrap_or_default(),
+            Some("Protocol") => session.protocol = reg_string(value).unwrap_or_default(),
+            Some("PortNumber") => {
+                session.port = value
+                    .strip_prefix("dword:")
+                    .and_then(|v| u32::from_str_radix(v, 16).ok())
+                    .unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+
+    sessions.iter().filter_map(|s| s.to_session()).collect()
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, tag: &str) -> &'a str {
+    node.children()
+        .find(|c| c.has_tag_name(tag))
+        .and_then(|c| c.text())
+        .unwrap_or("")
+        .trim()
+}
+
+/// `PuttyConType` is 0 for "use the saved session", otherwise PuTTY's
+/// protocol list shifted by one (raw, telnet, rlogin, ssh, serial)
+fn mtputty_server(node: roxmltree::Node) -> Option<Session> {
+    let port: u16 = child_text(node, "Port").parse().unwrap_or(0);
+
+    let connection_type = match child_text(node, "PuttyConType") {
+        "2" => ConnectionType::Telnet,
+        "4" => ConnectionType::SSH,
+        "0" | "" if port == ConnectionType::Telnet.default_port() => ConnectionType::Telnet,
+        "0" | "" => ConnectionType::SSH,
+        _ => return None,
+    };
+
+    let addr = Address::parse(child_text(node, "ServerName")).ok()?;
+    let user = match child_text(node, "UserName") {
+        "" => addr.user,
+        u => Some(u.to_string()),
+    };
+    let name = match child_text(node, "DisplayName") {
+        "" => addr.host.clone(),
+        n => n.to_string(),
+    };
+    let port = if port == 0 {
+        connection_type.default_port()
+    } else {
+        port
+    };
+
+    Some(Session::new(name, user, addr.host, port, connection_type))
+}
+
+fn walk_mtputty(node: roxmltree::Node, group: &mut SessionGroup) {
+    for child in node.children().filter(|c| c.has_tag_name("Node")) {
+        match child.attribute("Type") {
+            Some("0") => {
+                let mut folder =
+                    SessionGroup::new(child_text(child, "DisplayName").to_string(), Vec::new());
+                walk_mtputty(child, &mut folder);
+
+                match group.groups.iter_mut().find(|g| g.name == folder.name) {
+                    Some(g) => g.merge(folder),
+                    None => group.groups.push(folder),
+                }
+            }
+            Some("1") => {
+                if let Some(session) = mtputty_server(child) {
+                    group.sessions.push(session);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// parses `mtputty.xml`, top level folders become session groups and the
+/// folders within them child groups, servers outside of any folder are put
+/// in a group of their own
+pub fn parse_mtputty(content: &str) -> io::Result<Vec<SessionGroup>> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| invalid_data(e.to_string()))?;
+    let mut root = SessionGroup::new(MTPUTTY_GROUP_NAME.to_string(), Vec::new());
+
+    for putty in doc.descendants().filter(|n| n.has_tag_name("Putty")) {
+        walk_mtputty(putty, &mut root);
+    }
+
+    root.prune();
+
+    let mut groups = Vec::new();
+    if !root.sessions.is_empty() {
+        groups.push(SessionGroup::new(
+            MTPUTTY_GROUP_NAME.to_string(),
+            std::mem::take(&mut root.sessions),
+        ));
+    }
+    groups.extend(root.groups);
+
+    Ok(groups)
+}
+
+/// imports a PuTTY `.reg` export or a MTPuTTY `.xml` file
+pub fn import(path: &Path) -> io::Result<Vec<SessionGroup>> {
+    let content = decode_reg(&fs::read(path)?)?;
+
+    if content.trim_start().starts_with('<') {
+        return parse_mtputty(&content);
+    }
+
+    let sessions = parse_reg(&content);
+    if sessions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(vec![SessionGroup::new(
+        PUTTY_GROUP_NAME.to_string(),
+        sessions,
+    )])
+}
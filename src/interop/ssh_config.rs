@@ -0,0 +1,532 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::session_core::{
+    connection_type::ConnectionType, forward::Forward, jump_host::JumpHost, session::Session,
+    session_group::SessionGroup, ssh_options::split_args,
+};
+use crate::ui::config::{Config, NodeId};
+
+/// same limit as OpenSSH uses for nested `Include`s
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Name of the group that receives the hosts of the top level config file
+pub const DEFAULT_GROUP_NAME: &str = "ssh_config";
+
+/// A concrete `Host` alias with all matching blocks applied
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SshHost {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_files: Vec<String>,
+    pub proxy_jump: Option<String>,
+    pub forwards: Vec<Forward>,
+    /// file the `Host` line of the alias is in
+    pub source: PathBuf,
+}
+
+impl SshHost {
+    pub fn to_session(&self) -> Session {
+        let host = self.host_name.clone().unwrap_or(self.alias.clone());
+
+        let mut session = Session::new(
+            self.alias.clone(),
+            self.user.clone(),
+            host,
+            self.port.unwrap_or(ConnectionType::SSH.default_port()),
+            ConnectionType::SSH,
+        );
+
+        // ssh tries every identity file, gcoma only keeps the first one
+        session.ssh.identity_file = self.identity_files.first().cloned();
+        session.ssh.forwards = self.forwards.clone();
+        session
+    }
+}
+
+struct Block {
+    patterns: Vec<String>,
+    options: Vec<(String, Vec<String>)>,
+    source: PathBuf,
+}
+
+impl Block {
+    fn matches(&self, alias: &str) -> bool {
+        let mut matched = false;
+
+        for p in self.patterns.iter() {
+            if let Some(negated) = p.strip_prefix('!') {
+                if pattern_match(negated, alias) {
+                    return false;
+                }
+            } else if pattern_match(p, alias) {
+                matched = true;
+            }
+        }
+
+        matched
+    }
+}
+
+/// `*` and `?` wildcard matching as used by ssh_config host patterns
+pub fn pattern_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi].eq_ignore_ascii_case(&t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?', '!'])
+}
+
+/// splits a line into a lowercase keyword and its arguments, `Key value` and
+/// `Key=value` are both valid
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let key = line[..end].to_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    Some((key, split_args(rest)))
+}
+
+/// resolves the targets of an `Include`, relative paths are relative to `base_dir`
+fn include_paths(arg: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let path = paths::expand_tilde(arg);
+    let path = if path.is_relative() {
+        base_dir.join(path)
+    } else {
+        path
+    };
+
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if !is_pattern(&file_name) {
+        return vec![path];
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut found: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| pattern_match(&file_name, &e.file_name().to_string_lossy()))
+            .map(|e| e.path())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    found.sort();
+    found
+}
+
+fn parse_blocks(
+    content: &str,
+    source: &Path,
+    base_dir: &Path,
+    depth: usize,
+    blocks: &mut Vec<Block>,
+) -> io::Result<()> {
+    for line in content.lines() {
+        let (key, args) = match split_line(line) {
+            Some(l) => l,
+            None => continue,
+        };
+
+        match key.as_str() {
+            "host" => blocks.push(Block {
+                patterns: args,
+                options: Vec::new(),
+                source: source.to_path_buf(),
+            }),
+            // `Match` conditions can't be evaluated offline, its options are ignored
+            "match" => blocks.push(Block {
+                patterns: Vec::new(),
+                options: Vec::new(),
+                source: source.to_path_buf(),
+            }),
+            "include" => {
+                if depth >= MAX_INCLUDE_DEPTH {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("too many nested includes in {}", source.display()),
+                    ));
+                }
+
+                for arg in args.iter() {
+                    for path in include_paths(arg, base_dir) {
+                        // missing includes are not an error for ssh either
+                        let included = match fs::read_to_string(&path) {
+                            Ok(c) => c,
+                            Err(_) => continue,
+                        };
+
+                        // options at the top of the included file belong to the current block
+                        let patterns = blocks.last().map(|b| b.patterns.clone()).unwrap();
+                        blocks.push(Block {
+                            patterns: patterns.clone(),
+                            options: Vec::new(),
+                            source: path.clone(),
+                        });
+
+                        parse_blocks(&included, &path, base_dir, depth + 1, blocks)?;
+
+                        // and so are the ones following the `Include` line
+                        blocks.push(Block {
+                            patterns,
+                            options: Vec::new(),
+                            source: source.to_path_buf(),
+                        });
+                    }
+                }
+            }
+            _ => {
+                if !args.is_empty() {
+                    blocks.last_mut().unwrap().options.push((key, args));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// expands `%h` and `%%` in a `HostName` value
+fn expand_tokens(value: &str, alias: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('%', Some('h')) => {
+                out.push_str(alias);
+                chars.next();
+            }
+            ('%', Some('%')) => {
+                out.push('%');
+                chars.next();
+            }
+            (c, _) => out.push(c),
+        }
+    }
+
+    out
+}
+
+fn resolve_host(alias: &str, blocks: &[Block], source: &Path) -> SshHost {
+    let mut host = SshHost {
+        alias: alias.to_string(),
+        source: source.to_path_buf(),
+        ..SshHost::default()
+    };
+
+    // first obtained value wins, like in ssh
+    for block in blocks.iter().filter(|b| b.matches(alias)) {
+        for (key, args) in block.options.iter() {
+            let value = &args[0];
+
+            match key.as_str() {
+                "hostname" if host.host_name.is_none() => {
+                    host.host_name = Some(expand_tokens(value, alias));
+                }
+                "user" if host.user.is_none() => host.user = Some(value.clone()),
+                "port" if host.port.is_none() => host.port = value.parse().ok(),
+                "proxyjump" if host.proxy_jump.is_none() => {
+                    host.proxy_jump = Some(value.clone());
+                }
+                "identityfile" if !host.identity_files.contains(value) => {
+                    host.identity_files.push(value.clone());
+                }
+                "localforward" | "remoteforward" | "dynamicforward" => {
+                    let flag = key.to_uppercase().chars().next().unwrap();
+
+                    // `LocalForward 8080 db:5432` is the same as `-L 8080:db:5432`
+                    if let Ok(f) = Forward::parse(flag, &args.join(":")) {
+                        if !host.forwards.contains(&f) {
+                            host.forwards.push(f);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    host
+}
+
+/// parses ssh_config content, `base_dir` is where relative includes are looked up
+pub fn parse_str(content: &str, source: &Path, base_dir: &Path) -> io::Result<Vec<SshHost>> {
+    // options before the first `Host` line apply to every host
+    let mut blocks = vec![Block {
+        patterns: vec!["*".to_string()],
+        options: Vec::new(),
+        source: source.to_path_buf(),
+    }];
+
+    parse_blocks(content, source, base_dir, 0, &mut blocks)?;
+
+    let mut hosts: Vec<SshHost> = Vec::new();
+
+    for block in blocks.iter() {
+        for alias in block.patterns.iter().filter(|p| !is_pattern(p)) {
+            if hosts.iter().any(|h| &h.alias == alias) {
+                continue;
+            }
+
+            hosts.push(resolve_host(alias, &blocks, &block.source));
+        }
+    }
+
+    Ok(hosts)
+}
+
+/// relative `Include`s are looked up next to `path`, for `~/.ssh/config` that
+/// is `~/.ssh` like in ssh
+pub fn parse_file(path: &Path) -> io::Result<Vec<SshHost>> {
+    let content = fs::read_to_string(path)?;
+    let base_dir = match path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        Some(dir) => dir,
+        None => Path::new("."),
+    };
+
+    parse_str(&content, path, base_dir)
+}
+
+/// `~/.ssh/config`
+pub fn default_path() -> PathBuf {
+    paths::expand_tilde("~/.ssh/config")
+}
+
+fn group_name(source: &Path, root: &Path) -> String {
+    if source == root {
+        return DEFAULT_GROUP_NAME.to_string();
+    }
+
+    source
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or(DEFAULT_GROUP_NAME.to_string())
+}
+
+/// groups the hosts by the file they were declared in, `ProxyJump`s to other
+/// imported hosts become session references
+pub fn to_session_groups(hosts: &[SshHost], root: &Path) -> Vec<SessionGroup> {
+    let mut groups: Vec<SessionGroup> = Vec::new();
+
+    let session_path = |alias: &str| {
+        hosts
+            .iter()
+            .find(|h| h.alias == alias)
+            .map(|h| format!("{}/{}", group_name(&h.source, root), h.alias))
+    };
+
+    for host in hosts.iter() {
+        let name = group_name(&host.source, root);
+        let idx = match groups.iter().position(|sg| sg.name == name) {
+            Some(i) => i,
+            None => {
+                groups.push(SessionGroup::new(name, Vec::new()));
+                groups.len() - 1
+            }
+        };
+
+        let mut session = host.to_session();
+
+        if let Some(proxy_jump) = host.proxy_jump.as_deref().filter(|p| *p != "none") {
+            session.ssh.jump = proxy_jump
+                .split(',')
+                .filter_map(|j| match session_path(j) {
+                    Some(path) => Some(JumpHost::Session(path)),
+                    None => JumpHost::parse(j).ok(),
+                })
+                .collect();
+        }
+
+        groups[idx].sessions.push(session);
+    }
+
+    groups
+}
+
+pub fn import(path: &Path) -> io::Result<Vec<SessionGroup>> {
+    let hosts = parse_file(path)?;
+    Ok(to_session_groups(&hosts, path))
+}
+
+/// turns a group/session name into something usable as a `Host` alias
+fn alias_slug(s: &str) -> String {
+    let mut slug = String::new();
+
+    for c in s.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_matches('-').to_string()
+}
+
+/// `%` starts a token in ssh_config values, control characters are replaced
+/// too
+fn escape_tokens(s: &str) -> String {
+    one_line(s).replace('%', "%%")
+}
+
+/// replaces control characters with `?`, a newline in a name or value would
+/// otherwise start a new line with whatever option follows it
+fn one_line(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_control() { '?' } else { c })
+        .collect()
+}
+
+/// `LocalForward`/`RemoteForward` take the listen and target side as two arguments
+fn forward_line(f: &Forward) -> String {
+    let keyword = match f {
+        Forward::Local { .. } => "LocalForward",
+        Forward::Remote { .. } => "RemoteForward",
+        Forward::Dynamic { .. } => "DynamicForward",
+    };
+
+    match f.target_spec() {
+        Some(target) => format!("{} {} {}", keyword, f.listen_spec(), target),
+        None => format!("{} {}", keyword, f.listen_spec()),
+    }
+}
+
+/// renders the sessions of `cfg` as ssh_config `Host` blocks, telnet sessions
+/// are only listed as comments
+pub fn export(cfg: &Config) -> String {
+    let mut out = format!("# Generated by gcoma {}\n", env!("CARGO_PKG_VERSION"));
+    let mut aliases: Vec<String> = Vec::new();
+
+    for row in cfg.rows().iter() {
+        let s = match &row.id {
+            NodeId::Group(_) => {
+                out.push_str(&format!("\n# group: {}\n", one_line(&row.path)));
+                continue;
+            }
+            id => match cfg.effective_session(id) {
+                Some(s) => s,
+                None => continue,
+            },
+        };
+
+        if s.connection_type() != ConnectionType::SSH {
+            out.push_str(&format!(
+                "# skipped {} session '{}' ({})\n",
+                s.connection_type(),
+                one_line(&s.name),
+                one_line(&s.address())
+            ));
+            continue;
+        }
+
+        let base = alias_slug(&row.path);
+        let base = if base.is_empty() {
+            "host".to_string()
+        } else {
+            base
+        };
+
+        let mut alias = base.clone();
+        let mut i = 2;
+        while aliases.contains(&alias) {
+            alias = format!("{}-{}", base, i);
+            i += 1;
+        }
+        aliases.push(alias.clone());
+
+        out.push_str(&format!("Host {}\n", alias));
+        out.push_str(&format!("    HostName {}\n", escape_tokens(&s.host)));
+        if let Some(user) = &s.user {
+            out.push_str(&format!("    User {}\n", escape_tokens(user)));
+        }
+        out.push_str(&format!("    Port {}\n", s.port()));
+
+        match cfg.resolve_jump_chain(&s) {
+            Ok(chain) if !chain.is_empty() => {
+                out.push_str(&format!(
+                    "    ProxyJump {}\n",
+                    escape_tokens(&chain.join(","))
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => out.push_str(&format!("    # ProxyJump skipped, {}\n", one_line(&e))),
+        }
+
+        if let Some(identity_file) = &s.ssh.identity_file {
+            out.push_str(&format!(
+                "    IdentityFile \"{}\"\n",
+                one_line(identity_file)
+            ));
+        }
+        if s.ssh.forward_agent {
+            out.push_str("    ForwardAgent yes\n");
+        }
+        if s.ssh.forward_x11 {
+            out.push_str("    ForwardX11 yes\n");
+        }
+        for f in s.ssh.forwards.iter() {
+            out.push_str(&format!("    {}\n", one_line(&forward_line(f))));
+        }
+        for o in s.ssh.options.iter() {
+            if let Some((key, value)) = o.split_once('=') {
+                out.push_str(&format!(
+                    "    {} {}\n",
+                    one_line(key.trim()),
+                    one_line(value.trim())
+                ));
+            }
+        }
+        if !s.ssh.flags.is_empty() {
+            out.push_str(&format!(
+                "    # ssh flags: {}\n",
+                one_line(&s.ssh.flags.join(" "))
+            ));
+        }
+        if !s.ssh.extra_args.is_empty() {
+            out.push_str(&format!(
+                "    # remote command: {}\n",
+                one_line(&s.ssh.extra_args.join(" "))
+            ));
+        }
+    }
+
+    out
+}
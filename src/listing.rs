@@ -0,0 +1,140 @@
+use serde::Serialize;
+
+use crate::ui::config::Config;
+
+/// Output formats of `--list`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListFormat {
+    Json,
+    Csv,
+    Tsv,
+    Table,
+}
+
+impl ListFormat {
+    pub const NAMES: [&'static str; 4] = ["json", "csv", "tsv", "table"];
+
+    pub fn parse(s: &str) -> Option<ListFormat> {
+        match s {
+            "json" => Some(ListFormat::Json),
+            "csv" => Some(ListFormat::Csv),
+            "tsv" => Some(ListFormat::Tsv),
+            "table" => Some(ListFormat::Table),
+            _ => None,
+        }
+    }
+}
+
+/// One session of the listing, with the defaults of its groups applied
+#[derive(Serialize)]
+pub struct ListEntry {
+    /// position in the listing, what `--connect` accepts
+    pub index: usize,
+    /// `group/.../group` path
+    pub group: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub connection_type: String,
+    pub user: Option<String>,
+    pub host: String,
+    pub port: u16,
+}
+
+const HEADER: [&str; 7] = ["index", "group", "name", "type", "user", "host", "port"];
+
+impl ListEntry {
+    fn fields(&self) -> [String; 7] {
+        [
+            self.index.to_string(),
+            self.group.clone(),
+            self.name.clone(),
+            self.connection_type.clone(),
+            self.user.clone().unwrap_or_default(),
+            self.host.clone(),
+            self.port.to_string(),
+        ]
+    }
+}
+
+fn entries(cfg: &Config) -> Vec<ListEntry> {
+    cfg.sessions()
+        .into_iter()
+        .enumerate()
+        .map(|(index, (path, s))| ListEntry {
+            index,
+            group: path
+                .rsplit_once('/')
+                .map(|(group, _)| group.to_string())
+                .unwrap_or_default(),
+            name: s.name.clone(),
+            connection_type: s.connection_type().to_string(),
+            user: s.user.clone(),
+            port: s.port(),
+            host: s.host,
+        })
+        .collect()
+}
+
+/// quotes a field if it contains anything csv treats specially
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// tsv has no quoting, tabs and line breaks are replaced by spaces
+fn tsv_field(s: &str) -> String {
+    s.replace(['\t', '\n', '\r'], " ")
+}
+
+fn table(entries: &[ListEntry]) -> String {
+    let rows: Vec<[String; 7]> = entries.iter().map(|e| e.fields()).collect();
+    let mut widths = HEADER.map(|h| h.len());
+
+    for row in rows.iter() {
+        for (w, field) in widths.iter_mut().zip(row.iter()) {
+            *w = (*w).max(field.chars().count());
+        }
+    }
+
+    let header = HEADER.map(|h| h.to_uppercase());
+    let mut out = String::new();
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(field, w)| format!("{:<w$}", field, w = *w))
+            .collect();
+
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn render(cfg: &Config, format: ListFormat) -> serde_json::Result<String> {
+    let entries = entries(cfg);
+
+    Ok(match format {
+        ListFormat::Json => serde_json::to_string_pretty(&entries)? + "\n",
+        ListFormat::Csv | ListFormat::Tsv => {
+            let (sep, field): (&str, fn(&str) -> String) = match format {
+                ListFormat::Csv => (",", csv_field),
+                _ => ("\t", tsv_field),
+            };
+
+            let mut out = HEADER.join(sep) + "\n";
+            for e in entries.iter() {
+                let fields: Vec<String> = e.fields().iter().map(|f| field(f)).collect();
+                out.push_str(&fields.join(sep));
+                out.push('\n');
+            }
+            out
+        }
+        ListFormat::Table => table(&entries),
+    })
+}
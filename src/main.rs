@@ -1,75 +1,151 @@
-use std::{fs, io};
+use std::fs;
 
+use clap::ArgMatches;
+
+use cli::ImportSource;
+use error::{Error, Result};
+use listing::ListFormat;
 use ui::config::Config;
 
 #[cfg(test)]
 mod tests;
 
 mod args;
+mod backup;
+mod cli;
+mod config_format;
+mod error;
+mod fuzzy;
+mod interop;
+mod listing;
+mod merge;
+mod migrations;
+mod paths;
 mod reqs_check;
 mod session_core;
 mod ui;
 
-fn load_cfg_from_file(cfg_path: &str) -> io::Result<Config> {
-    let cfg_str = fs::read_to_string(cfg_path)?;
-    let config = serde_json::from_str(&cfg_str)?;
-    Ok(config)
-}
+/// the default config file, an empty one is created if there is none yet
+fn default_cfg_path() -> Result<String> {
+    let path = paths::default_config_path().ok_or_else(|| {
+        Error::Invalid(
+            "no config file given and no config directory found, use --user-config".to_string(),
+        )
+    })?;
 
-fn main() -> io::Result<()> {
-    if !reqs_check::is_in_env("ssh") {
-        panic!("'ssh' is not found in PATH!");
-    }
-    if !reqs_check::is_in_env("telnet") {
-        panic!("'telnet' is not found in PATH!");
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::file(dir, e))?;
+        }
+        Config::new().save(&path.to_string_lossy())?;
     }
 
-    let matches = args::get_args();
-    let cfg_path = matches.get_one::<String>("user_config");
-
-    if cfg_path.is_some() {
-        let user_config = load_cfg_from_file(cfg_path.unwrap().as_str());
+    Ok(path.to_string_lossy().into_owned())
+}
 
-        let list_flag = matches.get_one::<bool>("list").unwrap_or(&false).to_owned();
-        let connect_idx = matches.get_one::<String>("connect");
-        let rm_sg = matches.get_one::<String>("remove");
+fn run() -> Result<()> {
+    let matches = args::get_args();
 
-        if list_flag {
-            let mut i = 0;
+    let cfg_path = match matches.get_one::<String>("user_config") {
+        Some(p) => p.clone(),
+        None => default_cfg_path()?,
+    };
+    let cfg_path = cfg_path.as_str();
+    // commands that change the config start from an empty one if there is
+    // no file yet, a file that can't be read or parsed is never overwritten
+    let user_config = || Config::load(cfg_path);
+    let new_or_user_config = || Config::load_or_new(cfg_path);
 
-            for sg in user_config?.session_groups.iter() {
-                println!("{}:", sg.name);
+    let arg = |m: &ArgMatches, id: &str| m.get_one::<String>(id).cloned().unwrap_or_default();
+    let flag = |m: &ArgMatches, id: &str| m.get_one::<bool>(id).is_some_and(|f| *f);
+    let format = |m: &ArgMatches| {
+        m.get_one::<String>("format")
+            .and_then(|f| ListFormat::parse(f))
+    };
 
-                for s in sg.sessions.iter() {
-                    println!("  {}. {}", i, s.name);
-                    i += 1;
-                }
-            }
-        } else if connect_idx.is_some() {
-            let mut idx: usize = connect_idx.unwrap().parse().unwrap();
-
-            for sg in user_config?.session_groups.iter() {
-                for s in sg.sessions.iter() {
-                    if idx == 0 {
-                        s.connect();
-                        break;
-                    }
-                    idx -= 1;
+    match matches.subcommand() {
+        Some(("list", m)) => cli::list(&user_config()?, format(m))?,
+        Some(("connect", m)) => {
+            let query: Vec<String> = m.get_many::<String>("query").unwrap().cloned().collect();
+            cli::connect(&user_config()?, &query.join(" "))?;
+        }
+        Some(("add-group", m)) => {
+            let ucfg = new_or_user_config()?;
+            cli::add_group(cfg_path, ucfg, &arg(m, "path"), m)?;
+        }
+        Some(("add-session", m)) => {
+            let ucfg = new_or_user_config()?;
+            let (group_path, name, address) = (arg(m, "path"), arg(m, "name"), arg(m, "address"));
+            let tags = m.get_one::<String>("tags");
+            cli::add_session(cfg_path, ucfg, &group_path, &name, &address, tags, m)?;
+        }
+        Some(("edit", m)) => cli::edit(cfg_path, user_config()?, &arg(m, "path"), m)?,
+        Some(("rm", m)) => cli::remove(cfg_path, user_config()?, &arg(m, "path"))?,
+        Some(("mv", m)) => {
+            cli::move_node(cfg_path, user_config()?, &arg(m, "path"), &arg(m, "dest"))?
+        }
+        Some(("import", m)) => {
+            let path = m.get_one::<String>("path");
+            let source = match (arg(m, "source").as_str(), path) {
+                ("putty", Some(p)) => ImportSource::Putty(p),
+                ("putty", None) => {
+                    return Err(Error::Invalid(
+                        "importing from putty needs the path of a .reg or mtputty.xml file"
+                            .to_string(),
+                    ))
                 }
-            }
-        } else if rm_sg.is_some() {
-            let mut ucfg = user_config.unwrap_or(Config::new());
-            let sg_name = rm_sg.unwrap().to_owned();
+                (_, p) => ImportSource::SshConfig(p.map_or("~/.ssh/config", |p| p.as_str())),
+            };
 
-            ucfg.session_groups.retain(|sg| sg.name != sg_name);
+            let ucfg = new_or_user_config()?;
+            cli::import(cfg_path, ucfg, source, flag(m, "dry_run"))?;
+        }
+        Some(("restore-backup", m)) => {
+            let backup = m.get_one::<String>("backup").map(|b| b.as_str());
+            cli::restore_backup(cfg_path, backup, flag(m, "list"))?;
+        }
+        Some(("convert", m)) => cli::convert(cfg_path, &user_config()?, &arg(m, "path"))?,
+        Some(("doctor", _)) => cli::doctor(cfg_path, user_config(), &reqs_check::search_path())?,
+        Some(("export", m)) => cli::export_ssh_config(&user_config()?, &arg(m, "path"))?,
+        _ => {
+            // the flags from before there were subcommands
+            if flag(&matches, "list") {
+                cli::list(&user_config()?, format(&matches))?;
+            } else if let Some(query) = matches.get_one::<String>("connect") {
+                cli::connect(&user_config()?, query)?;
+            } else if let Some(sg_path) = matches.get_one::<String>("remove") {
+                let mut ucfg = new_or_user_config()?;
 
-            ucfg.save(cfg_path.unwrap().as_str());
-        } else {
-            ui::view::display(cfg_path.unwrap().as_str(), user_config)?;
+                ucfg.remove_group(sg_path);
+
+                ucfg.save(cfg_path)?;
+            } else if let Some(p) = matches.get_one::<String>("import_ssh_config") {
+                let ucfg = new_or_user_config()?;
+                let source = ImportSource::SshConfig(p);
+                cli::import(cfg_path, ucfg, source, flag(&matches, "dry_run"))?;
+            } else if let Some(p) = matches.get_one::<String>("import_putty") {
+                let ucfg = new_or_user_config()?;
+                let source = ImportSource::Putty(p);
+                cli::import(cfg_path, ucfg, source, flag(&matches, "dry_run"))?;
+            } else if let Some(export_path) = matches.get_one::<String>("export_ssh_config") {
+                cli::export_ssh_config(&user_config()?, export_path)?;
+            } else if let Some(v) = matches.get_many::<String>("add_session") {
+                let v: Vec<&String> = v.collect();
+                let ucfg = new_or_user_config()?;
+                // the --add-session flag has no --tags, only the subcommand
+                cli::add_session(cfg_path, ucfg, v[0], v[1], v[2], None, &matches)?;
+            } else {
+                ui::view::display(cfg_path, new_or_user_config()?)?;
+            }
         }
-    } else {
-        panic!("No user config file specified!");
     }
 
     Ok(())
 }
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
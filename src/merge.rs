@@ -0,0 +1,164 @@
+use crate::session_core::{
+    session::Session,
+    session_group::{join_path, SessionGroup},
+};
+use crate::ui::config::Config;
+
+/// Result of merging two versions of a config that were changed independently
+pub struct Merged {
+    pub config: Config,
+    /// `group/.../name` paths that both sides changed differently, the own
+    /// version of them was kept
+    pub conflicts: Vec<String>,
+}
+
+/// three-way merge of a value, `None` means it doesn't exist (anymore),
+/// returns the merged value and whether both sides changed it differently
+fn pick<T: PartialEq + Clone>(
+    base: Option<&T>,
+    mine: Option<&T>,
+    theirs: Option<&T>,
+) -> (Option<T>, bool) {
+    if mine == theirs || theirs == base {
+        (mine.cloned(), false)
+    } else if mine == base {
+        (theirs.cloned(), false)
+    } else {
+        // a change wins over a removal, nothing edited is lost
+        (mine.or(theirs).cloned(), true)
+    }
+}
+
+/// names of `mine` in order, followed by the ones only `theirs` has
+fn names<'a, T>(mine: &'a [T], theirs: &'a [T], name: fn(&T) -> &str) -> Vec<&'a str> {
+    let mut names: Vec<&str> = mine.iter().map(name).collect();
+
+    for n in theirs.iter().map(name) {
+        if !names.contains(&n) {
+            names.push(n);
+        }
+    }
+
+    names
+}
+
+fn find<'a, T>(items: &'a [T], name: &str, item_name: fn(&T) -> &str) -> Option<&'a T> {
+    items.iter().find(|item| item_name(item) == name)
+}
+
+fn merge_sessions(
+    path: &str,
+    base: &[Session],
+    mine: &[Session],
+    theirs: &[Session],
+    conflicts: &mut Vec<String>,
+) -> Vec<Session> {
+    let name_of: fn(&Session) -> &str = |s| &s.name;
+    let mut merged = Vec::new();
+
+    for name in names(mine, theirs, name_of) {
+        let (session, conflict) = pick(
+            find(base, name, name_of),
+            find(mine, name, name_of),
+            find(theirs, name, name_of),
+        );
+
+        if conflict {
+            conflicts.push(join_path(path, name));
+        }
+        merged.extend(session);
+    }
+
+    merged
+}
+
+fn merge_groups(
+    prefix: &str,
+    base: &[SessionGroup],
+    mine: &[SessionGroup],
+    theirs: &[SessionGroup],
+    conflicts: &mut Vec<String>,
+) -> Vec<SessionGroup> {
+    let name_of: fn(&SessionGroup) -> &str = |g| &g.name;
+    let mut merged = Vec::new();
+
+    for name in names(mine, theirs, name_of) {
+        let (b, m, t) = (
+            find(base, name, name_of),
+            find(mine, name, name_of),
+            find(theirs, name, name_of),
+        );
+
+        match (m, t) {
+            (Some(m), Some(t)) => merged.push(merge_group(prefix, b, m, t, conflicts)),
+            // added or removed on one side, or removed on one side while
+            // something in it changed on the other
+            _ => {
+                let (group, conflict) = pick(b, m, t);
+
+                if conflict {
+                    conflicts.push(join_path(prefix, name));
+                }
+                merged.extend(group);
+            }
+        }
+    }
+
+    merged
+}
+
+fn merge_group(
+    prefix: &str,
+    base: Option<&SessionGroup>,
+    mine: &SessionGroup,
+    theirs: &SessionGroup,
+    conflicts: &mut Vec<String>,
+) -> SessionGroup {
+    let path = join_path(prefix, &mine.name);
+    let empty = SessionGroup::new(mine.name.clone(), Vec::new());
+    let base = base.unwrap_or(&empty);
+
+    let (defaults, conflict) = pick(
+        Some(&base.defaults),
+        Some(&mine.defaults),
+        Some(&theirs.defaults),
+    );
+    if conflict {
+        conflicts.push(path.clone());
+    }
+
+    SessionGroup {
+        name: mine.name.clone(),
+        defaults: defaults.unwrap_or_default(),
+        sessions: merge_sessions(
+            &path,
+            &base.sessions,
+            &mine.sessions,
+            &theirs.sessions,
+            conflicts,
+        ),
+        groups: merge_groups(&path, &base.groups, &mine.groups, &theirs.groups, conflicts),
+    }
+}
+
+/// merges the changes from `base` to `mine` and from `base` to `theirs`,
+/// groups and sessions are matched by name, a group or session both sides
+/// changed differently keeps the version of `mine`
+pub fn three_way(base: &Config, mine: &Config, theirs: &Config) -> Merged {
+    let mut conflicts = Vec::new();
+    let session_groups = merge_groups(
+        "",
+        &base.session_groups,
+        &mine.session_groups,
+        &theirs.session_groups,
+        &mut conflicts,
+    );
+
+    Merged {
+        config: Config {
+            version: mine.version.clone(),
+            session_groups,
+        },
+        conflicts,
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::session_core::address::Address;
+
+type Step = fn(&mut Value) -> Result<(), String>;
+
+/// `(version, step)` in release order, `step` upgrades a config saved by a
+/// gcoma older than `version` to the layout `version` writes, so the version
+/// has to be bumped whenever the layout changes
+const STEPS: [(&str, Step); 1] = [("2.1.0", data_to_address)];
+
+/// `major.minor.patch` of a gcoma version
+pub type Version = (u64, u64, u64);
+
+#[derive(Debug, PartialEq)]
+pub enum MigrationError {
+    /// the config was saved by a newer gcoma, its layout may be unknown to
+    /// this one
+    Newer(String),
+    /// the stored version isn't a `major.minor.patch` version
+    Version(String),
+    /// the config couldn't be upgraded to the layout of `version`
+    Step { version: String, message: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::Newer(version) => write!(
+                f,
+                "saved by gcoma {}, this is gcoma {}, upgrade gcoma to use it",
+                version,
+                env!("CARGO_PKG_VERSION")
+            ),
+            MigrationError::Version(version) => {
+                write!(f, "unknown gcoma version '{}'", version)
+            }
+            MigrationError::Step { version, message } => {
+                write!(
+                    f,
+                    "can't upgrade to the layout of gcoma {}: {}",
+                    version, message
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// parses `major.minor.patch`, a pre-release or build suffix is ignored
+pub fn parse_version(s: &str) -> Option<Version> {
+    let s = s.split(['-', '+']).next()?;
+    let mut parts = s.split('.').map(|p| p.parse::<u64>().ok());
+
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+            Some((major, minor, patch))
+        }
+        _ => None,
+    }
+}
+
+/// version of gcoma that saved a config as read from disk, configs from
+/// before the `version` field are 0.0.0
+pub fn version(cfg: &Value) -> Result<Version, MigrationError> {
+    let version = match cfg.get("version") {
+        Some(v) => v.as_str().map_or_else(|| v.to_string(), str::to_string),
+        None => return Ok((0, 0, 0)),
+    };
+
+    parse_version(&version).ok_or(MigrationError::Version(version))
+}
+
+/// upgrades `cfg` one step at a time up to the layout of gcoma `to`, fails if
+/// `cfg` was saved by a newer gcoma than this one
+pub fn migrate(cfg: &mut Value, to: &str) -> Result<(), MigrationError> {
+    // anything that isn't an object is left to serde to complain about
+    if !cfg.is_object() {
+        return Ok(());
+    }
+
+    let from = version(cfg)?;
+    if from > current() {
+        return Err(MigrationError::Newer(
+            cfg["version"].as_str().unwrap_or("").to_string(),
+        ));
+    }
+
+    let to = parse_version(to).ok_or_else(|| MigrationError::Version(to.to_string()))?;
+    for (version, step) in STEPS.iter() {
+        let v = parse_version(version).expect("migration versions are valid");
+        if from >= v || v > to {
+            continue;
+        }
+
+        step(cfg).map_err(|message| MigrationError::Step {
+            version: version.to_string(),
+            message,
+        })?;
+        cfg["version"] = Value::from(*version);
+    }
+
+    Ok(())
+}
+
+/// version of this gcoma
+pub fn current() -> Version {
+    parse_version(env!("CARGO_PKG_VERSION")).expect("the crate version is valid")
+}
+
+type SessionStep = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// calls `f` on every session object in `groups` and their child groups
+fn for_each_session(groups: Option<&mut Value>, f: SessionStep) -> Result<(), String> {
+    let groups = match groups.and_then(Value::as_array_mut) {
+        Some(groups) => groups,
+        None => return Ok(()),
+    };
+
+    for group in groups.iter_mut() {
+        if let Some(sessions) = group.get_mut("sessions").and_then(Value::as_array_mut) {
+            for session in sessions.iter_mut().filter_map(Value::as_object_mut) {
+                f(session)?;
+            }
+        }
+        for_each_session(group.get_mut("groups"), f)?;
+    }
+
+    Ok(())
+}
+
+/// 2.1.0: the `user@host:port` string in `data` becomes the `user`, `host`
+/// and `port` fields
+fn data_to_address(cfg: &mut Value) -> Result<(), String> {
+    for_each_session(cfg.get_mut("session_groups"), |session| {
+        let data = match session.remove("data") {
+            Some(data) => data,
+            None => return Ok(()),
+        };
+        if session.contains_key("host") {
+            return Ok(());
+        }
+
+        let name = session.get("name").and_then(Value::as_str).unwrap_or("");
+        let data = data
+            .as_str()
+            .ok_or_else(|| format!("session '{}': data is not a string", name))?;
+        let addr = Address::parse(data).map_err(|e| format!("session '{}': {}", name, e))?;
+
+        if let Some(user) = addr.user {
+            session.insert("user".to_string(), Value::from(user));
+        }
+        session.insert("host".to_string(), Value::from(addr.host));
+        if let Some(port) = addr.port {
+            session.insert("port".to_string(), Value::from(port));
+        }
+
+        Ok(())
+    })
+}
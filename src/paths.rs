@@ -0,0 +1,73 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::config_format::ConfigFormat;
+
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(target_family = "windows")]
+    let var = env::var_os("USERPROFILE");
+    #[cfg(not(target_family = "windows"))]
+    let var = env::var_os("HOME");
+
+    var.filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+/// replaces a leading `~` with the home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if path == "~" {
+        if let Some(home) = home_dir() {
+            return home;
+        }
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    PathBuf::from(path)
+}
+
+/// `$XDG_CONFIG_HOME`, `~/.config` if it isn't set, `%APPDATA%` on windows
+pub fn config_dir() -> Option<PathBuf> {
+    #[cfg(target_family = "windows")]
+    let var = env::var_os("APPDATA");
+    #[cfg(not(target_family = "windows"))]
+    let var = env::var_os("XDG_CONFIG_HOME");
+
+    match var.filter(|d| !d.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => home_dir().map(|h| h.join(".config")),
+    }
+}
+
+/// the config used without `--user-config`, `$GCOMA_CONFIG` or the first
+/// `gcoma/config.<json|toml|yaml|yml>` that exists in the config directory,
+/// `gcoma/config.json` if there is none
+pub fn default_config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("GCOMA_CONFIG").filter(|p| !p.is_empty()) {
+        return Some(expand_tilde(&path.to_string_lossy()));
+    }
+
+    let dir = config_dir()?.join(env!("CARGO_PKG_NAME"));
+    let existing = ConfigFormat::EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("config.{}", ext)))
+        .find(|p| p.exists());
+
+    Some(existing.unwrap_or_else(|| dir.join("config.json")))
+}
+
+/// replaces the home directory at the start of `path` with `~`, for display
+pub fn contract_tilde(path: &str) -> String {
+    let home = match home_dir() {
+        Some(h) => h.to_string_lossy().into_owned(),
+        None => return path.to_string(),
+    };
+
+    match path.strip_prefix(&home) {
+        Some(rest) if rest.is_empty() || rest.starts_with(std::path::MAIN_SEPARATOR) => {
+            format!("~{}", rest)
+        }
+        _ => path.to_string(),
+    }
+}
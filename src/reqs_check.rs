@@ -1,37 +1,57 @@
 use std::env;
 use std::fs;
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::session_core::connection_type::ConnectionType;
+
+/// the directories programs are searched in
+pub fn search_path() -> String {
+    env::var("PATH").unwrap_or_default()
+}
 
 #[cfg(target_family = "windows")]
-pub fn is_in_env(prog_name: &str) -> bool {
-    if let Ok(path) = env::var("PATH") {
-        for p in path.split(";") {
-            // assuming that the binary has an extension
-            for ext in env::var("PATHEXT").unwrap_or("exe".to_string()).split(";") {
-                let p_str = format!("{}{}{}", p, prog_name, ext);
-                if fs::metadata(p_str).is_ok() {
-                    return true;
-                }
-
-                let p_str_slash = format!("{}\\{}{}", p, prog_name, ext);
-                if fs::metadata(p_str_slash).is_ok() {
-                    return true;
-                }
+pub fn find_in_path(prog_name: &str, path: &str) -> Option<PathBuf> {
+    for p in path.split(";") {
+        // assuming that the binary has an extension
+        for ext in env::var("PATHEXT").unwrap_or("exe".to_string()).split(";") {
+            let p_str = format!("{}{}{}", p, prog_name, ext);
+            if fs::metadata(&p_str).is_ok() {
+                return Some(PathBuf::from(p_str));
+            }
+
+            let p_str_slash = format!("{}\\{}{}", p, prog_name, ext);
+            if fs::metadata(&p_str_slash).is_ok() {
+                return Some(PathBuf::from(p_str_slash));
             }
         }
     }
 
-    false
+    None
 }
 
 #[cfg(target_family = "unix")]
-pub fn is_in_env(prog_name: &str) -> bool {
-    if let Ok(path) = env::var("PATH") {
-        for p in path.split(':') {
-            let p_str = format!("{}/{}", p, prog_name);
-            if fs::metadata(p_str).is_ok() {
-                return true;
-            }
+pub fn find_in_path(prog_name: &str, path: &str) -> Option<PathBuf> {
+    for p in path.split(':') {
+        let p_str = format!("{}/{}", p, prog_name);
+        if fs::metadata(&p_str).is_ok() {
+            return Some(PathBuf::from(p_str));
         }
     }
-    false
+    None
+}
+
+/// fails if the client program of `connection_type` isn't in `PATH`
+pub fn require_client(connection_type: &ConnectionType) -> Result<(), Error> {
+    require_client_in(connection_type, &search_path())
+}
+
+/// like `require_client`, searching the directories in `path`
+pub fn require_client_in(connection_type: &ConnectionType, path: &str) -> Result<(), Error> {
+    let program = connection_type.to_string();
+
+    match find_in_path(&program, path) {
+        Some(_) => Ok(()),
+        None => Err(Error::MissingProgram(program)),
+    }
 }
@@ -1,3 +1,9 @@
+pub mod address;
 pub mod connection_type;
+pub mod forward;
+pub mod jump_host;
 pub mod session;
+pub mod session_defaults;
 pub mod session_group;
+pub mod ssh_options;
+pub mod tunnel;
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+/// A parsed `user@host:port` connection address
+#[derive(Debug, PartialEq, Clone)]
+pub struct Address {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AddressError {
+    Empty,
+    EmptyUser,
+    EmptyHost,
+    InvalidPort(String),
+    UnclosedBracket,
+    InvalidIpv6(String),
+    Trailing(String),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Empty => write!(f, "address is empty"),
+            AddressError::EmptyUser => write!(f, "user name before '@' is empty"),
+            AddressError::EmptyHost => write!(f, "host is empty"),
+            AddressError::InvalidPort(p) => write!(f, "'{}' is not a valid port", p),
+            AddressError::UnclosedBracket => write!(f, "missing closing ']' after IPv6 host"),
+            AddressError::InvalidIpv6(h) => write!(f, "'{}' is not a valid IPv6 address", h),
+            AddressError::Trailing(t) => write!(f, "unexpected '{}' after host", t),
+        }
+    }
+}
+
+impl Address {
+    pub fn parse(s: &str) -> Result<Address, AddressError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(AddressError::Empty);
+        }
+
+        let (user, rest) = match s.rfind('@') {
+            Some(0) => return Err(AddressError::EmptyUser),
+            Some(i) => (Some(s[..i].to_string()), &s[i + 1..]),
+            None => (None, s),
+        };
+
+        let (host, port) = if let Some(bracketed) = rest.strip_prefix('[') {
+            // RFC 3986 IP literal: [v6addr%zone]:port
+            let end = bracketed.find(']').ok_or(AddressError::UnclosedBracket)?;
+            let host = parse_ipv6_host(&bracketed[..end])?;
+
+            let port = match &bracketed[end + 1..] {
+                "" => None,
+                p => match p.strip_prefix(':') {
+                    Some(p) => Some(parse_port(p)?),
+                    None => return Err(AddressError::Trailing(p.to_string())),
+                },
+            };
+
+            (host, port)
+        } else if rest.matches(':').count() > 1 {
+            // bare IPv6 literal, a port can't be told apart from the last group
+            (parse_ipv6_host(rest)?, None)
+        } else {
+            match rest.rfind(':') {
+                Some(i) => (rest[..i].to_string(), Some(parse_port(&rest[i + 1..])?)),
+                None => (rest.to_string(), None),
+            }
+        };
+
+        if host.is_empty() {
+            return Err(AddressError::EmptyHost);
+        }
+
+        Ok(Address { user, host, port })
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Address::parse(s)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+
+        match self.port {
+            Some(port) => write!(f, "{}:{}", format_host(&self.host), port),
+            None => write!(f, "{}", format_host(&self.host)),
+        }
+    }
+}
+
+pub fn parse_port(s: &str) -> Result<u16, AddressError> {
+    match s.parse::<u16>() {
+        Ok(p) if p != 0 => Ok(p),
+        _ => Err(AddressError::InvalidPort(s.to_string())),
+    }
+}
+
+/// validates an IPv6 literal with an optional zone id, `%25` (RFC 6874) is
+/// accepted as the zone separator too
+fn parse_ipv6_host(s: &str) -> Result<String, AddressError> {
+    let host = s.replacen("%25", "%", 1);
+    let (addr, zone) = match host.split_once('%') {
+        Some((addr, zone)) => (addr, Some(zone)),
+        None => (host.as_str(), None),
+    };
+
+    if addr.parse::<Ipv6Addr>().is_err() || zone == Some("") {
+        return Err(AddressError::InvalidIpv6(s.to_string()));
+    }
+
+    Ok(host)
+}
+
+/// wraps IPv6 hosts in brackets so a port can follow them
+pub fn format_host(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// formats the parts of an address back into `user@host:port`
+pub fn format_address(user: Option<&str>, host: &str, port: u16) -> String {
+    let host = format_host(host);
+
+    match user {
+        Some(u) => format!("{}@{}:{}", u, host, port),
+        None => format!("{}:{}", host, port),
+    }
+}
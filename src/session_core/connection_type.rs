@@ -1,18 +1,30 @@
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
+use std::fmt;
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum ConnectionType {
     Telnet,
     SSH,
 }
 
-impl ToString for ConnectionType {
-    fn to_string(&self) -> String {
+impl ConnectionType {
+    pub const ALL: [ConnectionType; 2] = [ConnectionType::SSH, ConnectionType::Telnet];
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            ConnectionType::Telnet => 23,
+            ConnectionType::SSH => 22,
+        }
+    }
+}
+
+impl fmt::Display for ConnectionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ConnectionType::Telnet => "telnet".to_string(),
-            ConnectionType::SSH => "ssh".to_string(),
+            ConnectionType::Telnet => write!(f, "telnet"),
+            ConnectionType::SSH => write!(f, "ssh"),
         }
     }
 }
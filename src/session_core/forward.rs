@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+use super::address::format_host;
+
+/// A saved `-L`, `-R` or `-D` port forward
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Forward {
+    Local {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bind_address: Option<String>,
+        port: u16,
+        host: String,
+        host_port: u16,
+    },
+    Remote {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bind_address: Option<String>,
+        /// 0 lets the server pick a port
+        port: u16,
+        host: String,
+        host_port: u16,
+    },
+    Dynamic {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bind_address: Option<String>,
+        port: u16,
+    },
+}
+
+/// splits on `:` except inside `[...]`, brackets are removed
+fn split_spec(spec: &str) -> Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut cur = String::new();
+    let mut in_brackets = false;
+
+    for c in spec.chars() {
+        match c {
+            '[' if !in_brackets && cur.is_empty() => in_brackets = true,
+            ']' if in_brackets => in_brackets = false,
+            ':' if !in_brackets => parts.push(std::mem::take(&mut cur)),
+            c => cur.push(c),
+        }
+    }
+
+    if in_brackets {
+        return Err(format!("missing ']' in '{}'", spec));
+    }
+
+    parts.push(cur);
+    Ok(parts)
+}
+
+fn is_valid_host(host: &str) -> bool {
+    host.parse::<IpAddr>().is_ok()
+        || (!host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_'))
+}
+
+fn parse_bind_address(s: &str) -> Result<Option<String>, String> {
+    match s {
+        "" => Ok(None),
+        "*" | "localhost" => Ok(Some(s.to_string())),
+        s if s.parse::<IpAddr>().is_ok() => Ok(Some(s.to_string())),
+        s => Err(format!("'{}' is not a valid bind address", s)),
+    }
+}
+
+fn parse_host(s: &str) -> Result<String, String> {
+    match is_valid_host(s) {
+        true => Ok(s.to_string()),
+        false => Err(format!("'{}' is not a valid host", s)),
+    }
+}
+
+fn parse_port(s: &str, allow_zero: bool) -> Result<u16, String> {
+    match s.parse::<u16>() {
+        Ok(0) if !allow_zero => Err("port 0 is only allowed for remote forwards".to_string()),
+        Ok(p) => Ok(p),
+        Err(_) => Err(format!("'{}' is not a port between 1 and 65535", s)),
+    }
+}
+
+impl Forward {
+    /// parses the argument of `-L`/`-R` (`[bind_address:]port:host:hostport`)
+    /// or `-D` (`[bind_address:]port`), `flag` is `'L'`, `'R'` or `'D'`
+    pub fn parse(flag: char, spec: &str) -> Result<Forward, String> {
+        let parts = split_spec(spec.trim())?;
+        let parts: Vec<&str> = parts.iter().map(|p| p.as_str()).collect();
+
+        match (flag, parts.as_slice()) {
+            ('L' | 'R', [port, host, host_port]) => {
+                Forward::new_tunnel(flag, None, port, host, host_port)
+            }
+            ('L' | 'R', [bind, port, host, host_port]) => {
+                Forward::new_tunnel(flag, Some(bind), port, host, host_port)
+            }
+            ('D', [port]) => Ok(Forward::Dynamic {
+                bind_address: None,
+                port: parse_port(port, false)?,
+            }),
+            ('D', [bind, port]) => Ok(Forward::Dynamic {
+                bind_address: parse_bind_address(bind)?,
+                port: parse_port(port, false)?,
+            }),
+            ('L' | 'R' | 'D', _) => Err(format!("'{}' is not a valid -{} forward", spec, flag)),
+            _ => Err(format!("-{} is not a forward", flag)),
+        }
+    }
+
+    fn new_tunnel(
+        flag: char,
+        bind: Option<&str>,
+        port: &str,
+        host: &str,
+        host_port: &str,
+    ) -> Result<Forward, String> {
+        let bind_address = parse_bind_address(bind.unwrap_or(""))?;
+        let host = parse_host(host)?;
+        let host_port = parse_port(host_port, false)?;
+
+        match flag {
+            'L' => Ok(Forward::Local {
+                bind_address,
+                port: parse_port(port, false)?,
+                host,
+                host_port,
+            }),
+            _ => Ok(Forward::Remote {
+                bind_address,
+                port: parse_port(port, true)?,
+                host,
+                host_port,
+            }),
+        }
+    }
+
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Forward::Local { .. } => "-L",
+            Forward::Remote { .. } => "-R",
+            Forward::Dynamic { .. } => "-D",
+        }
+    }
+
+    /// `[bind_address:]port` side of the forward
+    pub fn listen_spec(&self) -> String {
+        let (bind_address, port) = match self {
+            Forward::Local {
+                bind_address, port, ..
+            }
+            | Forward::Remote {
+                bind_address, port, ..
+            }
+            | Forward::Dynamic { bind_address, port } => (bind_address, port),
+        };
+
+        match bind_address {
+            Some(b) => format!("{}:{}", format_host(b), port),
+            None => port.to_string(),
+        }
+    }
+
+    /// `host:hostport` side of the forward, dynamic forwards have none
+    pub fn target_spec(&self) -> Option<String> {
+        match self {
+            Forward::Local {
+                host, host_port, ..
+            }
+            | Forward::Remote {
+                host, host_port, ..
+            } => Some(format!("{}:{}", format_host(host), host_port)),
+            Forward::Dynamic { .. } => None,
+        }
+    }
+
+    /// the argument of the flag, as ssh expects it
+    pub fn spec(&self) -> String {
+        match self.target_spec() {
+            Some(target) => format!("{}:{}", self.listen_spec(), target),
+            None => self.listen_spec(),
+        }
+    }
+}
+
+impl fmt::Display for Forward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.flag(), self.spec())
+    }
+}
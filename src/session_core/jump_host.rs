@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::address::{Address, AddressError};
+
+/// One hop of a `ProxyJump` chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JumpHost {
+    /// another session, referenced as `group/session`
+    Session(String),
+    /// a plain `user@host:port` address
+    Host(String),
+}
+
+impl JumpHost {
+    /// addresses can't contain a `/`, so anything with one is a session reference
+    pub fn parse(s: &str) -> Result<JumpHost, AddressError> {
+        let s = s.trim();
+
+        if s.contains('/') {
+            return Ok(JumpHost::Session(s.to_string()));
+        }
+
+        Address::parse(s)?;
+        Ok(JumpHost::Host(s.to_string()))
+    }
+
+    pub fn parse_list(s: &str) -> Result<Vec<JumpHost>, AddressError> {
+        s.split(',').map(JumpHost::parse).collect()
+    }
+}
+
+impl fmt::Display for JumpHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JumpHost::Session(path) => write!(f, "{}", path),
+            JumpHost::Host(addr) => write!(f, "{}", addr),
+        }
+    }
+}
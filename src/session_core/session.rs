@@ -1,21 +1,48 @@
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
+use super::address::{self, Address, AddressError};
 use super::connection_type::ConnectionType;
+use super::session_defaults::SessionDefaults;
+use super::ssh_options::SshOptions;
+use crate::error::Error;
+use crate::reqs_check;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Session {
     pub name: String,
-    pub data: String,
-    pub connection_type: ConnectionType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub host: String,
+    /// `None` inherits the port of the group, or the default of the connection type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// `None` inherits the connection type of the group, or ssh
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_type: Option<ConnectionType>,
+    #[serde(default, skip_serializing_if = "SshOptions::is_empty")]
+    pub ssh: SshOptions,
+    /// free form labels, only used for searching
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl Session {
-    pub fn new(name: String, data: String, connection_type: ConnectionType) -> Session {
+    pub fn new(
+        name: String,
+        user: Option<String>,
+        host: String,
+        port: u16,
+        connection_type: ConnectionType,
+    ) -> Session {
         Session {
             name,
-            data,
-            connection_type,
+            user,
+            host,
+            port: Some(port),
+            connection_type: Some(connection_type),
+            ssh: SshOptions::default(),
+            tags: Vec::new(),
         }
     }
 
@@ -23,74 +50,155 @@ impl Session {
         SessionBuilder::new()
     }
 
-    pub fn get_user_name(&self) -> String {
-        let end = self.data.find('@').unwrap_or(0);
+    pub fn get_user_name(&self) -> &str {
+        self.user.as_deref().unwrap_or("")
+    }
 
-        self.data[..end].to_string()
+    pub fn connection_type(&self) -> ConnectionType {
+        self.connection_type.clone().unwrap_or(ConnectionType::SSH)
     }
 
-    pub fn get_ip(&self) -> String {
-        let mut start = self.data.find('@').unwrap_or(0);
-        let end = self.data.find(':').unwrap_or(self.data.len());
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(self.connection_type().default_port())
+    }
 
-        if start != 0 {
-            start += 1;
+    /// fills what the session doesn't set itself from the defaults of its groups
+    pub fn inherit(&self, defaults: &SessionDefaults) -> Session {
+        Session {
+            name: self.name.clone(),
+            user: self.user.clone().or(defaults.user.clone()),
+            host: self.host.clone(),
+            port: defaults.inherit_port(self.port, self.connection_type.as_ref()),
+            connection_type: self
+                .connection_type
+                .clone()
+                .or(defaults.connection_type.clone()),
+            ssh: self.ssh.inherit(&defaults.ssh),
+            tags: self.tags.clone(),
         }
+    }
 
-        self.data[start..end].to_string()
+    /// true if both sessions would connect to the same place
+    pub fn same_target(&self, other: &Session) -> bool {
+        self.connection_type() == other.connection_type()
+            && self.user == other.user
+            && self.host.eq_ignore_ascii_case(&other.host)
+            && self.port() == other.port()
     }
 
-    pub fn get_port(&self) -> String {
-        let start = self.data.find(':').unwrap_or(0);
+    /// `user@host:port` representation of the session
+    pub fn address(&self) -> String {
+        address::format_address(self.user.as_deref(), &self.host, self.port())
+    }
 
-        if start == 0 && self.connection_type == ConnectionType::SSH {
-            return "22".to_string();
-        } else if start == 0 && self.connection_type == ConnectionType::Telnet {
-            return "23".to_string();
+    /// ssh arguments up to and including the destination
+    fn ssh_args(&self, jump_chain: &[String]) -> Vec<String> {
+        let mut args = self.ssh.to_args();
+
+        if !jump_chain.is_empty() {
+            args.push("-J".to_string());
+            args.push(jump_chain.join(","));
         }
 
-        let end = self.data.find('/').unwrap_or(self.data.len());
+        args.push("-p".to_string());
+        args.push(self.port().to_string());
 
-        self.data[start + 1..end].to_string()
-    }
+        if let Some(usr_name) = &self.user {
+            args.push("-l".to_string());
+            args.push(usr_name.clone());
+        }
 
-    pub fn connect(&self) {
-        let prog = self.connection_type.to_string();
-        let mut args: Vec<String> = vec![self.get_ip()];
+        args.push(self.host.clone());
+        args
+    }
 
-        match self.connection_type {
+    /// `jump_chain` is the resolved `-J` destination list
+    pub fn connect_args(&self, jump_chain: &[String]) -> Vec<String> {
+        match self.connection_type() {
             ConnectionType::SSH => {
-                args.push("-p".to_string());
-                args.push(self.get_port());
-
-                let usr_name = self.get_user_name();
-                if !usr_name.is_empty() {
-                    args.push("-l".to_string());
-                    args.push(usr_name);
-                }
-            }
-            ConnectionType::Telnet => {
-                args.push(self.get_port());
+                let mut args = self.ssh_args(jump_chain);
+                args.extend(self.ssh.extra_args.iter().cloned());
+                args
             }
+            ConnectionType::Telnet => vec![self.host.clone(), self.port().to_string()],
+        }
+    }
+
+    /// arguments for a background `ssh -N` that only keeps the forwards open,
+    /// it must not prompt for anything as there is no terminal to prompt on
+    pub fn tunnel_args(&self, jump_chain: &[String]) -> Result<Vec<String>, String> {
+        if self.connection_type() != ConnectionType::SSH {
+            return Err(format!("'{}' is not an ssh session", self.name));
         }
+        if self.ssh.forwards.is_empty() {
+            return Err(format!("'{}' has no port forwards", self.name));
+        }
+
+        let mut args: Vec<String> = [
+            "-N",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "BatchMode=yes",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+
+        args.extend(self.ssh_args(jump_chain));
+        Ok(args)
+    }
 
-        let mut child = Command::new(prog).args(&args).spawn().unwrap();
-        let _ = child.wait().unwrap();
+    /// splits a comma separated tag list, empty tags are dropped
+    pub fn parse_tags(s: &str) -> Vec<String> {
+        s.split(',')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect()
+    }
+
+    /// runs `ssh` or `telnet` in the current terminal until it exits
+    pub fn connect(&self, jump_chain: &[String]) -> Result<(), Error> {
+        reqs_check::require_client(&self.connection_type())?;
+
+        let program = self.connection_type().to_string();
+        let spawn_error = |source| Error::Spawn {
+            program: program.clone(),
+            source,
+        };
+
+        let mut child = Command::new(&program)
+            .args(self.connect_args(jump_chain))
+            .spawn()
+            .map_err(spawn_error)?;
+        child.wait().map_err(spawn_error)?;
+
+        Ok(())
     }
 }
 
 pub struct SessionBuilder {
     name: String,
-    data: String,
-    connection_type: ConnectionType,
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+    /// `None` inherits the connection type of the group
+    connection_type: Option<ConnectionType>,
+    ssh: SshOptions,
+    tags: Vec<String>,
 }
 
 impl SessionBuilder {
     fn new() -> SessionBuilder {
         SessionBuilder {
             name: "".to_string(),
-            data: "".to_string(),
-            connection_type: ConnectionType::SSH,
+            user: None,
+            host: "".to_string(),
+            port: None,
+            connection_type: None,
+            ssh: SshOptions::default(),
+            tags: Vec::new(),
         }
     }
 
@@ -99,21 +207,44 @@ impl SessionBuilder {
         self
     }
 
-    pub fn data(&mut self, data: String) -> &mut SessionBuilder {
-        self.data = data;
-        self
+    /// sets user, host and port from a `user@host:port` string
+    pub fn address(&mut self, address: &str) -> Result<&mut SessionBuilder, AddressError> {
+        let addr = Address::parse(address)?;
+
+        self.user = addr.user;
+        self.host = addr.host;
+        self.port = addr.port;
+        Ok(self)
     }
 
     pub fn connection_type(&mut self, connection_type: ConnectionType) -> &mut SessionBuilder {
-        self.connection_type = connection_type;
+        self.connection_type = Some(connection_type);
+        self
+    }
+
+    pub fn get_connection_type(&self) -> Option<&ConnectionType> {
+        self.connection_type.as_ref()
+    }
+
+    pub fn ssh_options(&mut self, ssh: SshOptions) -> &mut SessionBuilder {
+        self.ssh = ssh;
+        self
+    }
+
+    pub fn tags(&mut self, tags: Vec<String>) -> &mut SessionBuilder {
+        self.tags = tags;
         self
     }
 
     pub fn build(&self) -> Session {
-        Session::new(
-            self.name.clone(),
-            self.data.clone(),
-            self.connection_type.clone(),
-        )
+        Session {
+            name: self.name.clone(),
+            user: self.user.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            connection_type: self.connection_type.clone(),
+            ssh: self.ssh.clone(),
+            tags: self.tags.clone(),
+        }
     }
 }
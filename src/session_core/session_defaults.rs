@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use super::connection_type::ConnectionType;
+use super::ssh_options::SshOptions;
+
+/// Settings of a group that its sessions (and child groups) use unless they
+/// set them themselves
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct SessionDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_type: Option<ConnectionType>,
+    #[serde(default, skip_serializing_if = "SshOptions::is_empty")]
+    pub ssh: SshOptions,
+}
+
+impl SessionDefaults {
+    pub fn is_empty(&self) -> bool {
+        *self == SessionDefaults::default()
+    }
+
+    /// the port for something that sets `port` and `connection_type` itself,
+    /// the port of the defaults is only used while the connection type stays
+    /// the same, a different type falls back to its own default port
+    pub fn inherit_port(
+        &self,
+        port: Option<u16>,
+        connection_type: Option<&ConnectionType>,
+    ) -> Option<u16> {
+        let ssh = ConnectionType::SSH;
+        let inherited = self.connection_type.as_ref().unwrap_or(&ssh);
+
+        match connection_type {
+            Some(t) if t != inherited => port,
+            _ => port.or(self.port),
+        }
+    }
+
+    /// the defaults of a child group, `self` being the ones of its parent
+    pub fn merge(&self, child: &SessionDefaults) -> SessionDefaults {
+        SessionDefaults {
+            user: child.user.clone().or(self.user.clone()),
+            port: self.inherit_port(child.port, child.connection_type.as_ref()),
+            connection_type: child
+                .connection_type
+                .clone()
+                .or(self.connection_type.clone()),
+            ssh: child.ssh.inherit(&self.ssh),
+        }
+    }
+}
@@ -1,21 +1,100 @@
 use serde::{Deserialize, Serialize};
 
 use super::session::{Session, SessionBuilder};
+use super::session_defaults::SessionDefaults;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct SessionGroup {
     pub name: String,
+    #[serde(default, skip_serializing_if = "SessionDefaults::is_empty")]
+    pub defaults: SessionDefaults,
     pub sessions: Vec<Session>,
+    /// child groups, listed after the sessions of the group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<SessionGroup>,
 }
 
 impl SessionGroup {
     pub fn new(name: String, sessions: Vec<Session>) -> SessionGroup {
-        SessionGroup { name, sessions }
+        SessionGroup {
+            name,
+            defaults: SessionDefaults::default(),
+            sessions,
+            groups: Vec::new(),
+        }
     }
 
     pub fn builder() -> SessionGroupBuilder {
         SessionGroupBuilder::new()
     }
+
+    /// true if neither the group nor any of its children have sessions
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty() && self.groups.iter().all(|g| g.is_empty())
+    }
+
+    /// all sessions of the group and its children with their `group/.../session`
+    /// path, in display order, `inherited` are the defaults of the parent groups
+    pub fn walk_sessions(
+        &self,
+        prefix: &str,
+        inherited: &SessionDefaults,
+        out: &mut Vec<(String, Session)>,
+    ) {
+        let path = join_path(prefix, &self.name);
+        let defaults = inherited.merge(&self.defaults);
+
+        for s in self.sessions.iter() {
+            out.push((join_path(&path, &s.name), s.inherit(&defaults)));
+        }
+
+        for g in self.groups.iter() {
+            g.walk_sessions(&path, &defaults, out);
+        }
+    }
+
+    /// keeps the sessions (of children too) `f` returns true for, `f` gets
+    /// their `group/.../session` path as well
+    pub fn retain_sessions<F: FnMut(&str, &Session) -> bool>(&mut self, prefix: &str, f: &mut F) {
+        let path = join_path(prefix, &self.name);
+        self.sessions.retain(|s| f(&join_path(&path, &s.name), s));
+
+        for g in self.groups.iter_mut() {
+            g.retain_sessions(&path, f);
+        }
+    }
+
+    /// drops child groups without sessions
+    pub fn prune(&mut self) {
+        self.groups.retain(|g| !g.is_empty());
+
+        for g in self.groups.iter_mut() {
+            g.prune();
+        }
+    }
+
+    /// adds the sessions of `other`, child groups with the same name are merged
+    pub fn merge(&mut self, other: SessionGroup) {
+        if self.defaults.is_empty() {
+            self.defaults = other.defaults;
+        }
+        self.sessions.extend(other.sessions);
+
+        for child in other.groups.into_iter() {
+            match self.groups.iter_mut().find(|g| g.name == child.name) {
+                Some(g) => g.merge(child),
+                None => self.groups.push(child),
+            }
+        }
+    }
+}
+
+pub fn join_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
 }
 
 pub struct SessionGroupBuilder {
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+use super::forward::Forward;
+use super::jump_host::JumpHost;
+
+/// Extra settings only used for ssh sessions
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct SshOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    /// `-o` options in `Key=Value` form
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub forward_agent: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub forward_x11: bool,
+    /// `ProxyJump` chain, resolved to `-J` when connecting
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump: Vec<JumpHost>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forwards: Vec<Forward>,
+    /// any other ssh flags with their values, passed before the destination
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+    /// the remote command, passed after the destination
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+/// splits a string into words, double quotes group words with spaces
+pub fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut cur = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut cur));
+                    has_arg = false;
+                }
+            }
+            c => {
+                cur.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if has_arg {
+        args.push(cur);
+    }
+
+    args
+}
+
+/// quotes `arg` if `split_args` would split it
+fn quote_arg(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(char::is_whitespace) {
+        format!("\"{}\"", arg)
+    } else {
+        arg.to_string()
+    }
+}
+
+fn parse_jump(s: &str) -> Result<Vec<JumpHost>, String> {
+    JumpHost::parse_list(s).map_err(|e| format!("jump host '{}': {}", s, e))
+}
+
+/// ssh flags that take a value, from the option string ssh passes to getopt
+const VALUE_FLAGS: &str = "BDEFIJLOPQRSWbceilmopw";
+
+fn parse_forward(flag: &str, spec: &str) -> Result<Forward, String> {
+    Forward::parse(flag.chars().nth(1).unwrap_or(' '), spec)
+}
+
+impl SshOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == SshOptions::default()
+    }
+
+    /// parses ssh style flags (`-i file -o Key=Value -A -X -J a,b -L spec`),
+    /// other flags are kept as they are, everything after `--` or the first
+    /// argument that isn't a flag is the remote command
+    pub fn parse(s: &str) -> Result<SshOptions, String> {
+        let mut opts = SshOptions::default();
+        let mut args = split_args(s).into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-A" => opts.forward_agent = true,
+                "-X" => opts.forward_x11 = true,
+                "-i" | "-o" | "-J" | "-L" | "-R" | "-D" => {
+                    let value = args
+                        .next()
+                        .ok_or(format!("'{}' is missing its argument", arg))?;
+
+                    match arg.as_str() {
+                        "-i" => opts.identity_file = Some(value),
+                        "-o" => opts.options.push(value),
+                        "-J" => opts.jump.extend(parse_jump(&value)?),
+                        flag => opts.forwards.push(parse_forward(flag, &value)?),
+                    }
+                }
+                "--" => opts.extra_args.extend(args.by_ref()),
+                a if a.starts_with("-i") => opts.identity_file = Some(a[2..].to_string()),
+                a if a.starts_with("-o") => opts.options.push(a[2..].to_string()),
+                a if a.starts_with("-J") => opts.jump.extend(parse_jump(&a[2..])?),
+                a if a.starts_with("-L") || a.starts_with("-R") || a.starts_with("-D") => {
+                    opts.forwards.push(parse_forward(&a[..2], &a[2..])?)
+                }
+                a if a.len() > 1 && a.starts_with('-') => {
+                    let value_flag = a[1..]
+                        .char_indices()
+                        .find(|(_, c)| VALUE_FLAGS.contains(*c));
+
+                    match value_flag {
+                        Some((_, 'p')) => return Err("use the port of the session, not -p".into()),
+                        Some((_, 'l')) => return Err("use the user of the session, not -l".into()),
+                        // the value is the next argument unless it follows the flag
+                        Some((i, _)) if i + 2 == a.len() => {
+                            let value = args
+                                .next()
+                                .ok_or(format!("'{}' is missing its argument", a))?;
+                            opts.flags.push(arg);
+                            opts.flags.push(value);
+                        }
+                        _ => opts.flags.push(arg),
+                    }
+                }
+                _ => {
+                    opts.extra_args.push(arg);
+                    opts.extra_args.extend(args.by_ref());
+                }
+            }
+        }
+
+        if let Some(o) = opts.options.iter().find(|o| !o.contains('=')) {
+            return Err(format!("option '{}' is not in Key=Value form", o));
+        }
+
+        Ok(opts)
+    }
+
+    /// fills what isn't set from the options of the group, forwards are never
+    /// inherited as every session would try to listen on the same ports, and
+    /// neither is the remote command
+    pub fn inherit(&self, defaults: &SshOptions) -> SshOptions {
+        // ssh uses the first value of an option, so the own ones go first
+        let mut options = self.options.clone();
+        options.extend(defaults.options.iter().cloned());
+
+        SshOptions {
+            identity_file: self
+                .identity_file
+                .clone()
+                .or(defaults.identity_file.clone()),
+            options,
+            forward_agent: self.forward_agent || defaults.forward_agent,
+            forward_x11: self.forward_x11 || defaults.forward_x11,
+            jump: match self.jump.is_empty() {
+                true => defaults.jump.clone(),
+                false => self.jump.clone(),
+            },
+            forwards: self.forwards.clone(),
+            flags: match self.flags.is_empty() {
+                true => defaults.flags.clone(),
+                false => self.flags.clone(),
+            },
+            extra_args: self.extra_args.clone(),
+        }
+    }
+
+    /// arguments that go before the destination, except the jump chain as
+    /// that needs the config to be resolved
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(identity_file) = &self.identity_file {
+            args.push("-i".to_string());
+            args.push(identity_file.clone());
+        }
+
+        for o in self.options.iter() {
+            args.push("-o".to_string());
+            args.push(o.clone());
+        }
+
+        if self.forward_agent {
+            args.push("-A".to_string());
+        }
+        if self.forward_x11 {
+            args.push("-X".to_string());
+        }
+
+        for f in self.forwards.iter() {
+            args.push(f.flag().to_string());
+            args.push(f.spec());
+        }
+
+        args.extend(self.flags.iter().cloned());
+        args
+    }
+
+    /// the options as `parse` reads them, used to pre-fill the edit form
+    pub fn to_args_string(&self) -> String {
+        let mut args = self.to_args();
+
+        if !self.jump.is_empty() {
+            let jump: Vec<String> = self.jump.iter().map(|j| j.to_string()).collect();
+            args.push("-J".to_string());
+            args.push(jump.join(","));
+        }
+
+        if !self.extra_args.is_empty() {
+            args.push("--".to_string());
+            args.extend(self.extra_args.iter().cloned());
+        }
+
+        args.iter()
+            .map(|a| quote_arg(a))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
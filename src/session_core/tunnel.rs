@@ -0,0 +1,180 @@
+use std::fmt;
+use std::io;
+use std::process::{Child, Command, Stdio};
+
+use super::connection_type::ConnectionType;
+use super::session::Session;
+use crate::error::Error;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TunnelStatus {
+    Running,
+    /// exit code, `None` if it was killed by a signal
+    Exited(Option<i32>),
+    Stopped,
+    Failed(String),
+}
+
+impl fmt::Display for TunnelStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TunnelStatus::Running => write!(f, "running"),
+            TunnelStatus::Exited(Some(code)) => write!(f, "exited with code {}", code),
+            TunnelStatus::Exited(None) => write!(f, "killed"),
+            TunnelStatus::Stopped => write!(f, "stopped"),
+            TunnelStatus::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// A background `ssh -N` process that keeps the forwards of a session open
+pub struct Tunnel {
+    /// `group/session` path of the session
+    pub path: String,
+    pub status: TunnelStatus,
+    child: Option<Child>,
+}
+
+impl Tunnel {
+    pub fn is_running(&self) -> bool {
+        self.status == TunnelStatus::Running
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            self.status = TunnelStatus::Stopped;
+        }
+    }
+}
+
+pub struct TunnelManager {
+    tunnels: Vec<Tunnel>,
+    /// started with the tunnel arguments, `ssh` except in tests
+    program: String,
+}
+
+impl TunnelManager {
+    pub fn new() -> TunnelManager {
+        TunnelManager::with_program(&ConnectionType::SSH.to_string())
+    }
+
+    pub fn with_program(program: &str) -> TunnelManager {
+        TunnelManager {
+            tunnels: Vec::new(),
+            program: program.to_string(),
+        }
+    }
+
+    pub fn tunnels(&self) -> &[Tunnel] {
+        &self.tunnels
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Tunnel> {
+        self.tunnels.iter().find(|t| t.path == path)
+    }
+
+    /// spawns the tunnel of `session`, a finished tunnel of the same session is replaced
+    pub fn start(
+        &mut self,
+        path: &str,
+        session: &Session,
+        jump_chain: &[String],
+    ) -> Result<(), String> {
+        if self.get(path).is_some_and(|t| t.is_running()) {
+            return Err(format!("tunnel of '{}' is already running", path));
+        }
+
+        let args = session.tunnel_args(jump_chain)?;
+        let spawned = Command::new(&self.program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let tunnel = match spawned {
+            Ok(child) => Tunnel {
+                path: path.to_string(),
+                status: TunnelStatus::Running,
+                child: Some(child),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(Error::MissingProgram(self.program.clone()).to_string())
+            }
+            Err(e) => Tunnel {
+                path: path.to_string(),
+                status: TunnelStatus::Failed(e.to_string()),
+                child: None,
+            },
+        };
+
+        self.tunnels.retain(|t| t.path != path);
+        self.tunnels.push(tunnel);
+        Ok(())
+    }
+
+    pub fn stop(&mut self, path: &str) {
+        if let Some(t) = self.tunnels.iter_mut().find(|t| t.path == path) {
+            t.stop();
+        }
+    }
+
+    /// stops and forgets the tunnels of a session or group that was removed
+    pub fn remove(&mut self, path: &str) {
+        let prefix = format!("{}/", path);
+
+        for t in self.tunnels.iter_mut() {
+            if t.path == path || t.path.starts_with(&prefix) {
+                t.stop();
+            }
+        }
+        self.tunnels
+            .retain(|t| t.path != path && !t.path.starts_with(&prefix));
+    }
+
+    /// keeps the tunnels of a session or group that was renamed or moved from
+    /// `old` to `new` under their new path
+    pub fn rename(&mut self, old: &str, new: &str) {
+        for t in self.tunnels.iter_mut() {
+            if t.path == old {
+                t.path = new.to_string();
+            } else if let Some(rest) = t.path.strip_prefix(&format!("{}/", old)) {
+                t.path = format!("{}/{}", new, rest);
+            }
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for t in self.tunnels.iter_mut() {
+            t.stop();
+        }
+    }
+
+    /// picks up the exit status of tunnels that have finished on their own
+    pub fn poll(&mut self) {
+        for t in self.tunnels.iter_mut() {
+            let exited = match t.child.as_mut().map(|c| c.try_wait()) {
+                Some(Ok(Some(status))) => TunnelStatus::Exited(status.code()),
+                Some(Err(e)) => TunnelStatus::Failed(e.to_string()),
+                _ => continue,
+            };
+
+            t.child = None;
+            t.status = exited;
+        }
+    }
+}
+
+impl Default for TunnelManager {
+    fn default() -> TunnelManager {
+        TunnelManager::new()
+    }
+}
+
+impl Drop for TunnelManager {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
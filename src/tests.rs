@@ -1 +1,69 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::session_core::{
+    address::AddressError, connection_type::ConnectionType, session::Session,
+};
+
+pub mod backup_tests;
+pub mod config_format_tests;
+pub mod edit_state_tests;
+pub mod fuzzy_tests;
+pub mod listing_tests;
+pub mod merge_tests;
+pub mod migration_tests;
+pub mod putty_tests;
+pub mod reqs_check_tests;
 pub mod session_core_tests;
+pub mod ssh_config_tests;
+pub mod view_state_tests;
+
+/// a session to `address` (`user@host:port`) without any other settings
+pub fn session_to(
+    name: String,
+    address: &str,
+    connection_type: ConnectionType,
+) -> Result<Session, AddressError> {
+    let mut sb = Session::builder();
+    sb.name(name).address(address)?;
+    sb.connection_type(connection_type);
+    Ok(sb.build())
+}
+
+/// an ssh session to `host`, which may also hold a user and a port
+pub fn session(name: &str, host: &str) -> Session {
+    session_to(name.to_string(), host, ConnectionType::SSH).unwrap()
+}
+
+/// `gcoma_<name>_<pid>` in the temp directory, deleted with everything in it
+/// when dropped, also when an assert fails
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let dir = std::env::temp_dir().join(format!("gcoma_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    pub fn join(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+
+    /// `join` as the string the config functions take
+    pub fn path(&self, file: &str) -> String {
+        self.join(file).to_string_lossy().into_owned()
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+
+use crate::backup;
+use crate::tests::TempDir;
+use crate::ui::config::Config;
+
+#[test]
+fn timestamp_test() {
+    assert_eq!(backup::timestamp(0), "19700101-000000-000");
+    assert_eq!(backup::timestamp(951_782_400_000), "20000229-000000-000");
+    assert_eq!(backup::timestamp(1_700_000_000_123), "20231114-221320-123");
+}
+
+#[test]
+fn save_backup_test() {
+    let dir = TempDir::new("backup");
+    let path = dir.join("config.json");
+    let path_str = dir.path("config.json");
+
+    let mut cfg = Config::new();
+    cfg.save(&path_str).unwrap();
+    assert!(backup::list(&path).is_empty());
+
+    // an unchanged config isn't written again
+    cfg.save(&path_str).unwrap();
+    assert!(backup::list(&path).is_empty());
+
+    let first = fs::read_to_string(&path).unwrap();
+    cfg.add_group("work", Default::default()).unwrap();
+    cfg.save(&path_str).unwrap();
+    cfg.add_group("home", Default::default()).unwrap();
+    cfg.save(&path_str).unwrap();
+
+    let backups = backup::list(&path);
+    assert_eq!(backups.len(), 2);
+    assert_eq!(fs::read_to_string(&backups[1]).unwrap(), first);
+    assert_eq!(Config::load(&path_str).unwrap().session_groups.len(), 2);
+
+    // only the config and its backups, no temporary file is left
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    let saved = backup::list(&path);
+    for day in 1..=backup::KEEP {
+        let name = format!("config.json.200001{:02}-000000-000.bak", day);
+        fs::write(dir.join(&name), "").unwrap();
+    }
+    backup::rotate(&path, backup::KEEP).unwrap();
+
+    // the two oldest are gone, the ones `save` made are the newest
+    let backups = backup::list(&path);
+    assert_eq!(backups.len(), backup::KEEP);
+    assert_eq!(backups[..2], saved[..]);
+    assert!(!dir.join("config.json.20000102-000000-000.bak").exists());
+    assert!(dir.join("config.json.20000103-000000-000.bak").exists());
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn write_atomic_test() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = TempDir::new("atomic");
+    let target = dir.join("dotfiles.json");
+    let link = dir.join("config.json");
+    fs::write(&target, "old").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+    symlink(&target, &link).unwrap();
+
+    backup::write_atomic(&link, "new").unwrap();
+
+    // the link still points to the file, which was replaced
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    let mode = fs::metadata(&target).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+}
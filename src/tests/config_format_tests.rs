@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::Path;
+
+use crate::config_format::{has_yaml_comments, ConfigFormat};
+use crate::error::Error;
+use crate::session_core::{
+    connection_type::ConnectionType, session_defaults::SessionDefaults, ssh_options::SshOptions,
+};
+use crate::tests::{session, session_to, TempDir};
+use crate::ui::config::Config;
+
+fn full_config() -> Config {
+    let mut cfg = Config::new();
+    let defaults = SessionDefaults {
+        user: Some("deploy".to_string()),
+        port: Some(2200),
+        connection_type: None,
+        ssh: SshOptions::parse("-A -o StrictHostKeyChecking=no").unwrap(),
+    };
+    cfg.add_group("prod/eu", defaults).unwrap();
+
+    let mut bastion = session_to(
+        "bastion".to_string(),
+        "admin@[fe80::1]",
+        ConnectionType::SSH,
+    )
+    .unwrap();
+    bastion.tags = vec!["edge".to_string(), "yes".to_string()];
+    cfg.add_session("prod/eu", bastion).unwrap();
+
+    let mut web = session_to("web".to_string(), "10.0.0.5", ConnectionType::SSH).unwrap();
+    web.connection_type = None;
+    web.ssh = SshOptions::parse(
+        r#"-J prod/eu/bastion,edge@10.0.0.1 -L 8080:localhost:80 -D 1080 -- uptime "a b""#,
+    )
+    .unwrap();
+    cfg.add_session("prod/eu", web).unwrap();
+
+    let sw = session_to("sw".to_string(), "no:2323", ConnectionType::Telnet).unwrap();
+    cfg.add_session("lab", sw).unwrap();
+    cfg
+}
+
+#[test]
+fn from_extension_test() {
+    let of = |p: &str| ConfigFormat::from_extension(Path::new(p));
+
+    assert_eq!(of("config.json"), Some(ConfigFormat::Json));
+    assert_eq!(of("~/dotfiles/gcoma.TOML"), Some(ConfigFormat::Toml));
+    assert_eq!(of("config.yaml"), Some(ConfigFormat::Yaml));
+    assert_eq!(of("config.yml"), Some(ConfigFormat::Yaml));
+    assert_eq!(of("config"), None);
+    assert_eq!(
+        ConfigFormat::of(Path::new("config.json.bak")),
+        ConfigFormat::Json
+    );
+}
+
+#[test]
+fn round_trip_test() {
+    let dir = TempDir::new("format");
+    let cfg = full_config();
+
+    for ext in ConfigFormat::EXTENSIONS.iter() {
+        let path = dir.path(&format!("config.{}", ext));
+
+        cfg.save(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), cfg, "{}", ext);
+    }
+
+    let toml = fs::read_to_string(dir.join("config.toml")).unwrap();
+    assert!(toml.contains("[[session_groups.groups.sessions]]"));
+    // enums are written the same way as in json, not as yaml tags
+    let yaml = fs::read_to_string(dir.join("config.yaml")).unwrap();
+    assert!(yaml.contains("- session: prod/eu/bastion"));
+}
+
+#[test]
+fn parse_test() {
+    let toml = r#"
+        # comments are fine
+        version = "2.0.0"
+
+        [[session_groups]]
+        name = "old"
+
+        [[session_groups.sessions]]
+        name = "web"
+        data = "root@10.0.0.1:2222"
+        connection_type = "SSH"
+    "#;
+    let value = ConfigFormat::Toml.parse(toml).unwrap();
+    assert_eq!(value["session_groups"][0]["sessions"][0]["name"], "web");
+
+    // yaml tags are read like single key maps
+    let yaml = "jump:\n- !session prod/eu/bastion\n- host: edge@10.0.0.1\n";
+    let value = ConfigFormat::Yaml.parse(yaml).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({"jump": [{"session": "prod/eu/bastion"}, {"host": "edge@10.0.0.1"}]})
+    );
+
+    assert!(ConfigFormat::Toml.parse("session_groups = [").is_err());
+}
+
+#[test]
+fn save_toml_comments_test() {
+    let dir = TempDir::new("format_toml");
+    let path = dir.path("config.toml");
+    fs::write(
+        &path,
+        r#"# written by hand
+version = "2.1.0"
+
+# the office
+[[session_groups]]
+name = "work"
+
+# the way in
+[[session_groups.sessions]]
+name = "bastion"
+host = "10.0.0.1" # behind the vpn
+port = 2222
+tags = [
+    "edge", # reachable from outside
+]
+
+[[session_groups.sessions]]
+name = "db"
+host = "10.0.0.2"
+
+[[session_groups]]
+name = "home"
+sessions = []
+"#,
+    )
+    .unwrap();
+
+    let mut cfg = Config::load(&path).unwrap();
+    let id = cfg.find_node("work/bastion").unwrap();
+    cfg.remove(&id);
+    cfg.add_session("home", session("nas", "192.168.0.2"))
+        .unwrap();
+    let mut bastion = session("bastion", "10.0.0.9:2222");
+    bastion.tags = vec!["edge".to_string()];
+    cfg.add_session("work", bastion).unwrap();
+    cfg.save(&path).unwrap();
+
+    let saved = fs::read_to_string(&path).unwrap();
+    for comment in [
+        "# written by hand",
+        "# the office",
+        "# the way in",
+        "\"10.0.0.9\" # behind the vpn",
+        "# reachable from outside",
+    ] {
+        assert!(saved.contains(comment), "{} in\n{}", comment, saved);
+    }
+    assert_eq!(Config::load(&path).unwrap(), cfg);
+
+    // the comments moved to the end of the group with their session
+    assert!(saved.find("# the way in") > saved.find("name = \"db\""));
+}
+
+#[test]
+fn save_yaml_comments_test() {
+    let dir = TempDir::new("format_yaml");
+    let path = dir.path("config.yaml");
+    let yaml = "version: 2.1.0\nsession_groups:\n# the office\n- name: work\n  sessions: []\n";
+    fs::write(&path, yaml).unwrap();
+
+    let mut cfg = Config::load(&path).unwrap();
+    cfg.add_session("work", session("db", "10.0.0.2")).unwrap();
+    assert!(matches!(cfg.save(&path), Err(Error::Invalid(_))));
+    assert_eq!(fs::read_to_string(&path).unwrap(), yaml);
+
+    assert!(!has_yaml_comments("name: 'a #1'\nhost: \"b #2\"\nx: c#3\n"));
+    assert!(has_yaml_comments("name: a # 1\n"));
+    assert!(has_yaml_comments("  # indented\nname: a\n"));
+}
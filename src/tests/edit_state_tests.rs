@@ -0,0 +1,141 @@
+use tui_textarea::TextArea;
+
+use crate::session_core::{connection_type::ConnectionType, jump_host::JumpHost};
+use crate::ui::config::{Config, NodeId};
+use crate::ui::edit_state::EditState;
+
+const CONFIG: &str = r#"{"version":"2","session_groups":[{
+    "name":"work",
+    "sessions":[
+        {"name":"bastion","host":"10.0.0.1","connection_type":"SSH"},
+        {"name":"db","user":"pg","host":"10.0.0.2","port":2222,"connection_type":"SSH",
+         "ssh":{"identity_file":"~/.ssh/my key","jump":[{"session":"work/bastion"}],"flags":["-t"],"extra_args":["top"]}}
+    ]
+}]}"#;
+
+fn set(form: &mut EditState, field: usize, value: &str) {
+    form.fields[field].textarea = TextArea::new(vec![value.to_string()]);
+}
+
+#[test]
+fn edit_session_prefill_test() {
+    let cfg: Config = serde_json::from_str(CONFIG).unwrap();
+    let form = EditState::new(&cfg, NodeId::Session(vec![0], 1)).unwrap();
+    let values: Vec<&str> = form.fields.iter().map(|f| f.value()).collect();
+
+    assert_eq!(form.title(), "Edit session work/db");
+    assert_eq!(
+        values,
+        vec![
+            "db",
+            "ssh",
+            "pg@10.0.0.2:2222",
+            "-i \"~/.ssh/my key\" -t -J work/bastion -- top",
+            ""
+        ]
+    );
+
+    // saving an untouched form doesn't change anything
+    let mut edited = cfg.clone();
+    form.apply(&mut edited).unwrap();
+    assert_eq!(
+        serde_json::to_string(&edited).unwrap(),
+        serde_json::to_string(&cfg).unwrap()
+    );
+}
+
+#[test]
+fn edit_session_test() {
+    let mut cfg: Config = serde_json::from_str(CONFIG).unwrap();
+    let mut form = EditState::new(&cfg, NodeId::Session(vec![0], 0)).unwrap();
+
+    set(&mut form, 2, "[::1");
+    assert!(form.apply(&mut cfg).is_err());
+    assert_eq!(cfg.session_groups[0].sessions[0].host, "10.0.0.1");
+
+    set(&mut form, 0, "jump");
+    set(&mut form, 1, "telnet");
+    set(&mut form, 2, "admin@10.0.0.9");
+    form.apply(&mut cfg).unwrap();
+
+    let s = &cfg.session_groups[0].sessions[0];
+    assert_eq!(s.name, "jump");
+    assert!(s.connection_type() == ConnectionType::Telnet);
+    assert_eq!(s.address(), "admin@10.0.0.9:23");
+
+    // jump hosts follow the rename
+    assert_eq!(
+        cfg.session_groups[0].sessions[1].ssh.jump,
+        vec![JumpHost::Session("work/jump".to_string())]
+    );
+}
+
+#[test]
+fn edit_group_test() {
+    let mut cfg: Config = serde_json::from_str(CONFIG).unwrap();
+    let mut form = EditState::new(&cfg, NodeId::Group(vec![0])).unwrap();
+
+    set(&mut form, 0, "a/b");
+    assert!(form.apply(&mut cfg).is_err());
+
+    set(&mut form, 0, "office");
+    set(&mut form, 2, "deploy");
+    set(&mut form, 3, "70000");
+    assert!(form.apply(&mut cfg).is_err());
+
+    set(&mut form, 3, "8022");
+    form.apply(&mut cfg).unwrap();
+
+    assert_eq!(
+        cfg.find_session("office/bastion").unwrap().address(),
+        "deploy@10.0.0.1:8022"
+    );
+    assert_eq!(
+        cfg.session_groups[0].sessions[1].ssh.jump,
+        vec![JumpHost::Session("office/bastion".to_string())]
+    );
+}
+
+#[test]
+fn edit_rename_taken_test() {
+    let mut cfg: Config = serde_json::from_str(CONFIG).unwrap();
+    cfg.add_group("home", Default::default()).unwrap();
+    let before = cfg.clone();
+
+    let mut form = EditState::new(&cfg, NodeId::Session(vec![0], 1)).unwrap();
+    set(&mut form, 0, "bastion");
+    assert_eq!(
+        form.apply(&mut cfg),
+        Err("'work/bastion' already exists".to_string())
+    );
+
+    let mut form = EditState::new(&cfg, NodeId::Group(vec![1])).unwrap();
+    set(&mut form, 0, "work");
+    assert!(form.apply(&mut cfg).is_err());
+    assert_eq!(cfg, before);
+
+    // keeping the own name is fine
+    set(&mut form, 0, "home");
+    assert_eq!(form.apply(&mut cfg), Ok("home".to_string()));
+}
+
+#[test]
+fn edit_set_test() {
+    let mut cfg: Config = serde_json::from_str(CONFIG).unwrap();
+    let mut form = EditState::new(&cfg, NodeId::Group(vec![0])).unwrap();
+
+    assert!(form.set("name", "office"));
+    assert!(form.set("port", "2200"));
+    assert!(!form.set("address", "10.0.0.9"));
+    form.apply(&mut cfg).unwrap();
+
+    assert_eq!(cfg.session_groups[0].name, "office");
+    assert_eq!(cfg.session_groups[0].defaults.port, Some(2200));
+
+    let mut form = EditState::new(&cfg, NodeId::Session(vec![0], 0)).unwrap();
+    assert!(!form.set("user", "root"));
+    assert!(form.set("tags", "edge"));
+    form.apply(&mut cfg).unwrap();
+
+    assert_eq!(cfg.session_groups[0].sessions[0].tags, vec!["edge"]);
+}
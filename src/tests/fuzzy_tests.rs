@@ -0,0 +1,26 @@
+use crate::fuzzy;
+use crate::session_core::connection_type::ConnectionType;
+use crate::tests::session_to;
+
+#[test]
+fn score_test() {
+    assert!(fuzzy::score("pgs", "postgres").is_some());
+    assert!(fuzzy::score("PG", "postgres").is_some());
+    assert!(fuzzy::score("gp", "postgres").is_none());
+    assert_eq!(fuzzy::score("", "anything"), Some(10));
+
+    // substrings and word starts beat scattered matches
+    assert!(fuzzy::score("db", "prod/db") > fuzzy::score("db", "dashboard"));
+    assert!(fuzzy::score("web", "eu-web-1") > fuzzy::score("web", "w-e-b"));
+}
+
+#[test]
+fn score_session_test() {
+    let mut session = session_to("db".to_string(), "admin@10.0.1.1", ConnectionType::SSH).unwrap();
+    session.tags = vec!["postgres".to_string(), "eu".to_string()];
+
+    assert!(fuzzy::score_session("prod db", "work/prod/db", &session).is_some());
+    assert!(fuzzy::score_session("admin 10.0.1", "work/prod/db", &session).is_some());
+    assert!(fuzzy::score_session("pgs eu", "work/prod/db", &session).is_some());
+    assert!(fuzzy::score_session("prod mysql", "work/prod/db", &session).is_none());
+}
@@ -0,0 +1,50 @@
+use crate::listing::{self, ListFormat};
+use crate::ui::config::Config;
+
+const CONFIG: &str = r#"{"version":"2","session_groups":[{
+    "name":"work",
+    "defaults":{"user":"deploy"},
+    "sessions":[{"name":"db, primary","host":"10.0.0.1","port":2222}],
+    "groups":[{"name":"lab","sessions":[{"name":"switch","host":"fe80::1","connection_type":"Telnet"}]}]
+}]}"#;
+
+#[test]
+fn list_csv_tsv_test() {
+    let cfg: Config = serde_json::from_str(CONFIG).unwrap();
+
+    assert_eq!(
+        listing::render(&cfg, ListFormat::Csv).unwrap(),
+        "index,group,name,type,user,host,port\n\
+         0,work,\"db, primary\",ssh,deploy,10.0.0.1,2222\n\
+         1,work/lab,switch,telnet,deploy,fe80::1,23\n"
+    );
+    assert_eq!(
+        listing::render(&cfg, ListFormat::Tsv)
+            .unwrap()
+            .lines()
+            .nth(1),
+        Some("0\twork\tdb, primary\tssh\tdeploy\t10.0.0.1\t2222")
+    );
+}
+
+#[test]
+fn list_json_table_test() {
+    let cfg: Config = serde_json::from_str(CONFIG).unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&listing::render(&cfg, ListFormat::Json).unwrap()).unwrap();
+    assert_eq!(json[1]["group"], "work/lab");
+    assert_eq!(json[1]["type"], "telnet");
+    assert_eq!(json[1]["port"], 23);
+
+    let table = listing::render(&cfg, ListFormat::Table).unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(
+        lines[0],
+        "INDEX  GROUP     NAME         TYPE    USER    HOST      PORT"
+    );
+    assert_eq!(
+        lines[2],
+        "1      work/lab  switch       telnet  deploy  fe80::1   23"
+    );
+}
@@ -0,0 +1,107 @@
+use crate::merge;
+use crate::session_core::{session_defaults::SessionDefaults, session_group::SessionGroup};
+use crate::tests::session;
+use crate::ui::config::Config;
+
+fn base() -> Config {
+    let mut prod = SessionGroup::new("prod".to_string(), vec![session("db", "10.0.1.1")]);
+    prod.groups.push(SessionGroup::new(
+        "eu".to_string(),
+        vec![session("web", "10.0.2.1")],
+    ));
+
+    let mut work = SessionGroup::new("work".to_string(), vec![session("bastion", "10.0.0.1")]);
+    work.groups.push(prod);
+
+    let mut cfg = Config::new();
+    cfg.session_groups.push(work);
+    cfg.session_groups.push(SessionGroup::new(
+        "home".to_string(),
+        vec![session("nas", "192.168.0.2")],
+    ));
+    cfg
+}
+
+fn paths(cfg: &Config) -> Vec<String> {
+    cfg.sessions().into_iter().map(|(p, _)| p).collect()
+}
+
+#[test]
+fn merge_independent_changes_test() {
+    let base = base();
+
+    let mut mine = base.clone();
+    mine.add_session("work/prod", session("cache", "10.0.1.2"))
+        .unwrap();
+    mine.session_groups[1].sessions[0].host = "192.168.0.3".to_string();
+
+    let mut theirs = base.clone();
+    theirs.remove_group("work/prod/eu");
+    theirs
+        .add_session("lab", session("pi", "10.0.9.9"))
+        .unwrap();
+    theirs.session_groups[0].defaults = SessionDefaults {
+        user: Some("ops".to_string()),
+        ..SessionDefaults::default()
+    };
+
+    let merged = merge::three_way(&base, &mine, &theirs);
+
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(
+        paths(&merged.config),
+        vec![
+            "work/bastion",
+            "work/prod/db",
+            "work/prod/cache",
+            "home/nas",
+            "lab/pi"
+        ]
+    );
+    assert_eq!(
+        merged.config.find_session("home/nas").unwrap().host,
+        "192.168.0.3"
+    );
+    assert_eq!(
+        merged.config.session_groups[0].defaults.user.as_deref(),
+        Some("ops")
+    );
+}
+
+#[test]
+fn merge_conflicts_test() {
+    let base = base();
+
+    let mut mine = base.clone();
+    mine.session_groups[0].sessions[0].host = "10.0.0.100".to_string();
+    mine.remove_group("home");
+
+    let mut theirs = base.clone();
+    theirs.session_groups[0].sessions[0].host = "10.0.0.200".to_string();
+    theirs.session_groups[1].sessions[0].port = Some(2222);
+
+    let merged = merge::three_way(&base, &mine, &theirs);
+
+    assert_eq!(merged.conflicts, vec!["work/bastion", "home"]);
+    // mine wins a conflicting change, a change wins over a removal
+    assert_eq!(
+        merged.config.find_session("work/bastion").unwrap().host,
+        "10.0.0.100"
+    );
+    assert_eq!(merged.config.find_session("home/nas").unwrap().port(), 2222);
+}
+
+#[test]
+fn merge_same_change_test() {
+    let base = base();
+
+    let mut mine = base.clone();
+    mine.add_session("lab", session("pi", "10.0.9.9")).unwrap();
+    mine.remove_group("home");
+    let theirs = mine.clone();
+
+    let merged = merge::three_way(&base, &mine, &theirs);
+
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(merged.config, mine);
+}
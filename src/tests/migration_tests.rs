@@ -0,0 +1,103 @@
+use std::fs;
+
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::migrations::{self, MigrationError};
+use crate::tests::TempDir;
+use crate::ui::config::Config;
+
+const CONFIG_2_0_0: &str = include_str!("fixtures/config_2.0.0.json");
+const CONFIG_2_1_0: &str = include_str!("fixtures/config_2.1.0.json");
+
+fn fixture(json: &str) -> Value {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn version_test() {
+    assert_eq!(migrations::parse_version("2.1.0"), Some((2, 1, 0)));
+    assert_eq!(migrations::parse_version("10.0.3-beta.1"), Some((10, 0, 3)));
+    assert_eq!(migrations::parse_version("2.1"), None);
+    assert_eq!(migrations::parse_version("2.1.0.4"), None);
+    assert_eq!(migrations::parse_version("two"), None);
+
+    assert_eq!(migrations::version(&fixture(CONFIG_2_0_0)), Ok((2, 0, 0)));
+    assert_eq!(migrations::version(&fixture(CONFIG_2_1_0)), Ok((2, 1, 0)));
+    // from before the version was stored
+    assert_eq!(
+        migrations::version(&fixture(r#"{"session_groups":[]}"#)),
+        Ok((0, 0, 0))
+    );
+    assert_eq!(
+        migrations::version(&fixture(r#"{"version":2}"#)),
+        Err(MigrationError::Version("2".to_string()))
+    );
+}
+
+#[test]
+fn migrate_2_1_0_test() {
+    let mut cfg = fixture(CONFIG_2_0_0);
+    migrations::migrate(&mut cfg, "2.1.0").unwrap();
+    assert_eq!(cfg, fixture(CONFIG_2_1_0));
+
+    // already saved by 2.1.0, nothing changes
+    migrations::migrate(&mut cfg, "2.1.0").unwrap();
+    assert_eq!(cfg, fixture(CONFIG_2_1_0));
+
+    // steps after `to` are left out
+    let mut cfg = fixture(CONFIG_2_0_0);
+    migrations::migrate(&mut cfg, "2.0.5").unwrap();
+    assert_eq!(cfg, fixture(CONFIG_2_0_0));
+
+    let mut cfg = fixture(
+        r#"{"version":"2.0.0","session_groups":[{"name":"g","sessions":[
+            {"name":"old","data":"root@10.0.0.1:x","connection_type":"SSH"}]}]}"#,
+    );
+    assert!(matches!(
+        migrations::migrate(&mut cfg, "2.1.0"),
+        Err(MigrationError::Step { version, .. }) if version == "2.1.0"
+    ));
+}
+
+#[test]
+fn load_old_config_test() {
+    let dir = TempDir::new("migration_old");
+    let path = dir.path("old.json");
+    fs::write(&path, CONFIG_2_0_0).unwrap();
+    let cfg = Config::load(&path).unwrap();
+
+    let sessions = cfg.sessions();
+    assert_eq!(sessions.len(), 4);
+    assert_eq!(sessions[0].0, "servers/web");
+    assert_eq!(sessions[0].1.address(), "root@10.0.0.1:2222");
+    assert_eq!(sessions[1].1.port(), 2323);
+    assert_eq!(sessions[3].1.host, "fe80::1");
+
+    // saving writes the current layout and version
+    cfg.save(&path).unwrap();
+    let saved = fixture(&fs::read_to_string(&path).unwrap());
+    assert_eq!(migrations::version(&saved), Ok(migrations::current()));
+    assert_eq!(Config::load(&path).unwrap(), cfg);
+}
+
+#[test]
+fn newer_config_test() {
+    let dir = TempDir::new("migration_newer");
+    let path = dir.path("newer.json");
+    let (major, _, _) = migrations::current();
+    let json = format!(r#"{{"version":"{}.0.0","session_groups":[]}}"#, major + 1);
+    fs::write(&path, &json).unwrap();
+
+    match Config::load(&path) {
+        Err(e @ Error::Migration { .. }) => {
+            let msg = e.to_string();
+            assert!(msg.contains(&format!("saved by gcoma {}.0.0", major + 1)));
+            assert!(msg.contains("upgrade gcoma"));
+        }
+        other => panic!("expected a migration error, got {:?}", other.map(|_| ())),
+    }
+
+    // a newer config is never touched
+    assert_eq!(fs::read_to_string(&path).unwrap(), json);
+}
@@ -0,0 +1,121 @@
+use std::fs;
+
+use crate::interop::putty;
+use crate::session_core::connection_type::ConnectionType;
+use crate::tests::TempDir;
+
+const REG_EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions]
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+"PortNumber"=dword:00000016
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\web%20server]
+"HostName"="admin@10.0.0.1"
+"PortNumber"=dword:000008ae
+"Protocol"="ssh"
+"UserName"=""
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\switch]
+"HostName"="10.0.0.2"
+"PortNumber"=dword:00000017
+"Protocol"="telnet"
+"UserName"="ops"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\console]
+"HostName"="COM1"
+"Protocol"="serial"
+"#;
+
+const MTPUTTY_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<MTPutty version="1.0">
+  <Servers>
+    <Putty>
+      <Node Type="1">
+        <SavedSession>Default Settings</SavedSession>
+        <DisplayName>loose</DisplayName>
+        <ServerName>192.168.1.10</ServerName>
+        <PuttyConType>4</PuttyConType>
+        <Port>0</Port>
+        <UserName>root</UserName>
+      </Node>
+      <Node Type="0">
+        <DisplayName>eu</DisplayName>
+        <Node Type="0">
+          <DisplayName>prod</DisplayName>
+          <Node Type="1">
+            <DisplayName>db &amp; cache</DisplayName>
+            <ServerName>db.example.com</ServerName>
+            <PuttyConType>4</PuttyConType>
+            <Port>2222</Port>
+            <UserName></UserName>
+          </Node>
+          <Node Type="1">
+            <DisplayName>switch</DisplayName>
+            <ServerName>10.1.0.1</ServerName>
+            <PuttyConType>2</PuttyConType>
+            <Port>0</Port>
+          </Node>
+        </Node>
+      </Node>
+    </Putty>
+  </Servers>
+</MTPutty>
+"#;
+
+#[test]
+fn reg_test() {
+    let sessions = putty::parse_reg(REG_EXPORT);
+
+    assert_eq!(sessions.len(), 2);
+
+    assert_eq!(sessions[0].name, "web server");
+    assert_eq!(sessions[0].get_user_name(), "admin");
+    assert_eq!(sessions[0].host, "10.0.0.1");
+    assert_eq!(sessions[0].port(), 2222);
+
+    assert_eq!(sessions[1].name, "switch");
+    assert_eq!(sessions[1].get_user_name(), "ops");
+    assert_eq!(sessions[1].port(), 23);
+    assert!(sessions[1].connection_type() == ConnectionType::Telnet);
+}
+
+#[test]
+fn reg_utf16_test() {
+    let dir = TempDir::new("putty");
+    let path = dir.join("putty.reg");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(REG_EXPORT.encode_utf16().flat_map(|u| u.to_le_bytes()));
+    fs::write(&path, bytes).unwrap();
+
+    let groups = putty::import(&path).unwrap();
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].name, putty::PUTTY_GROUP_NAME);
+    assert_eq!(groups[0].sessions.len(), 2);
+}
+
+#[test]
+fn mtputty_test() {
+    let groups = putty::parse_mtputty(MTPUTTY_XML).unwrap();
+    let names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+
+    assert_eq!(names, vec![putty::MTPUTTY_GROUP_NAME, "eu"]);
+    assert!(groups[1].sessions.is_empty());
+    assert_eq!(groups[1].groups[0].name, "prod");
+
+    let loose = &groups[0].sessions[0];
+    assert_eq!(loose.address(), "root@192.168.1.10:22");
+
+    let prod = &groups[1].groups[0];
+    let db = &prod.sessions[0];
+    assert_eq!(db.name, "db & cache");
+    assert_eq!(db.user, None);
+    assert_eq!(db.port(), 2222);
+
+    let switch = &prod.sessions[1];
+    assert!(switch.connection_type() == ConnectionType::Telnet);
+    assert_eq!(switch.port(), 23);
+}
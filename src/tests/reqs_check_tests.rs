@@ -0,0 +1,42 @@
+use std::fs::File;
+
+use crate::cli;
+use crate::error::Error;
+use crate::reqs_check;
+use crate::session_core::connection_type::ConnectionType;
+use crate::tests::{session, session_to, TempDir};
+use crate::ui::config::Config;
+
+#[cfg(target_family = "unix")]
+#[test]
+fn client_in_path_test() {
+    let dir = TempDir::new("path");
+    File::create(dir.join("ssh")).unwrap();
+    let path = format!("{}:{}", dir.path("missing"), dir.path(""));
+
+    assert_eq!(
+        reqs_check::find_in_path("ssh", &path),
+        Some(dir.join("ssh"))
+    );
+    assert_eq!(reqs_check::find_in_path("telnet", &path), None);
+    assert_eq!(reqs_check::find_in_path("ssh", ""), None);
+
+    assert!(reqs_check::require_client_in(&ConnectionType::SSH, &path).is_ok());
+    assert!(matches!(
+        reqs_check::require_client_in(&ConnectionType::Telnet, &path),
+        Err(Error::MissingProgram(p)) if p == "telnet"
+    ));
+
+    // a missing client only matters when a session uses it
+    let mut cfg = Config::new();
+    cfg.add_session("work", session("bastion", "10.0.0.1"))
+        .unwrap();
+    assert!(cli::doctor("config.json", Ok(cfg.clone()), &path).is_ok());
+
+    let sw = session_to("sw".to_string(), "10.0.9.9", ConnectionType::Telnet).unwrap();
+    cfg.add_session("lab", sw).unwrap();
+    assert!(matches!(
+        cli::doctor("config.json", Ok(cfg), &path),
+        Err(Error::MissingProgram(p)) if p == "telnet"
+    ));
+}
@@ -1,5 +1,15 @@
+use crate::error::Error;
+use crate::session_core::address::{Address, AddressError};
 use crate::session_core::connection_type::ConnectionType;
+use crate::session_core::forward::Forward;
+use crate::session_core::jump_host::JumpHost;
 use crate::session_core::session::Session;
+use crate::session_core::session_defaults::SessionDefaults;
+use crate::session_core::session_group::SessionGroup;
+use crate::session_core::ssh_options::SshOptions;
+use crate::session_core::tunnel::{TunnelManager, TunnelStatus};
+use crate::tests::{session_to, TempDir};
+use crate::ui::config::{Config, NodeId};
 
 macro_rules! SESSION_NAME {
     () => {
@@ -9,109 +19,797 @@ macro_rules! SESSION_NAME {
 
 #[test]
 fn ip_test1() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "192.168.0.1:23".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "192.168.0.1:23", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_ip(), "192.168.0.1");
+    assert_eq!(session.host, "192.168.0.1");
 }
 
 #[test]
 fn ip_test2() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "user@192.168.0.1:23".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "user@192.168.0.1:23", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_ip(), "192.168.0.1");
+    assert_eq!(session.host, "192.168.0.1");
 }
 
 #[test]
 fn ip_test3() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "user@192.168.0.1".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "user@192.168.0.1", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_ip(), "192.168.0.1");
+    assert_eq!(session.host, "192.168.0.1");
 }
 
 #[test]
 fn ip_test4() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "192.168.0.1".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "192.168.0.1", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_ip(), "192.168.0.1");
+    assert_eq!(session.host, "192.168.0.1");
 }
 
 #[test]
 fn port_test1() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "192.168.0.1:23".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "192.168.0.1:23", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_port(), "23");
+    assert_eq!(session.port(), 23);
 }
 
 #[test]
 fn port_test2() {
-    let session = Session::new(
+    let session = session_to(
         SESSION_NAME!(),
-        "user@192.168.0.1:2222".to_string(),
+        "user@192.168.0.1:2222",
         ConnectionType::SSH,
-    );
-    assert_eq!(session.get_port(), "2222");
+    )
+    .unwrap();
+    assert_eq!(session.port(), 2222);
 }
 
 #[test]
 fn port_test3() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "user@192.168.0.1".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "user@192.168.0.1", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_port(), "22");
+    assert_eq!(session.port(), 22);
 }
 
 #[test]
 fn port_test4() {
-    let session = Session::new(
-        SESSION_NAME!(),
-        "192.168.0.1".to_string(),
-        ConnectionType::SSH,
-    );
+    let session = session_to(SESSION_NAME!(), "192.168.0.1", ConnectionType::SSH).unwrap();
 
-    assert_eq!(session.get_port(), "22");
+    assert_eq!(session.port(), 22);
+}
+
+#[test]
+fn port_test5() {
+    assert_eq!(
+        Address::parse("user@192.168.0.1:99999"),
+        Err(AddressError::InvalidPort("99999".to_string()))
+    );
+    assert_eq!(
+        Address::parse("192.168.0.1:"),
+        Err(AddressError::InvalidPort("".to_string()))
+    );
 }
 
 #[test]
 fn user_name_test1() {
-    let session = Session::new(
+    let session = session_to(
         SESSION_NAME!(),
-        "user_name@192.168.0.1:2222".to_string(),
+        "user_name@192.168.0.1:2222",
         ConnectionType::SSH,
-    );
+    )
+    .unwrap();
 
     assert_eq!(session.get_user_name(), "user_name");
 }
 
 #[test]
 fn user_name_test2() {
-    let session = Session::new(
+    let session = session_to(SESSION_NAME!(), "192.168.0.1:2222", ConnectionType::SSH).unwrap();
+
+    assert_eq!(session.user, None);
+    assert_eq!(session.get_user_name(), "");
+}
+
+#[test]
+fn user_name_test3() {
+    assert_eq!(Address::parse("@192.168.0.1"), Err(AddressError::EmptyUser));
+    assert_eq!(Address::parse("user@"), Err(AddressError::EmptyHost));
+}
+
+#[test]
+fn serialize_test() {
+    let session = session_to(SESSION_NAME!(), "10.0.0.1", ConnectionType::Telnet).unwrap();
+    let json = serde_json::to_string(&session).unwrap();
+
+    assert_eq!(
+        json,
+        r#"{"name":"TEST_SESSION","host":"10.0.0.1","connection_type":"Telnet"}"#
+    );
+    assert_eq!(session.connect_args(&[]), vec!["10.0.0.1", "23"]);
+}
+
+#[test]
+fn ip_test5() {
+    let session = session_to(
         SESSION_NAME!(),
-        "192.168.0.1:2222".to_string(),
+        "admin@[fe80::1%eth0]:2222",
         ConnectionType::SSH,
+    )
+    .unwrap();
+
+    assert_eq!(session.get_user_name(), "admin");
+    assert_eq!(session.host, "fe80::1%eth0");
+    assert_eq!(session.port(), 2222);
+    assert_eq!(session.address(), "admin@[fe80::1%eth0]:2222");
+}
+
+#[test]
+fn ip_test6() {
+    let session = session_to(SESSION_NAME!(), "::1", ConnectionType::SSH).unwrap();
+
+    assert_eq!(session.host, "::1");
+    assert_eq!(session.port(), 22);
+
+    let session = session_to(SESSION_NAME!(), "user@2001:db8::2", ConnectionType::Telnet).unwrap();
+
+    assert_eq!(session.host, "2001:db8::2");
+    assert_eq!(session.port(), 23);
+}
+
+#[test]
+fn ip_test7() {
+    let addr = Address::parse("[fe80::1%25eth0]").unwrap();
+
+    assert_eq!(addr.host, "fe80::1%eth0");
+    assert_eq!(addr.port, None);
+}
+
+#[test]
+fn ip_test8() {
+    assert_eq!(
+        Address::parse("[fe80::1:22"),
+        Err(AddressError::UnclosedBracket)
+    );
+    assert_eq!(
+        Address::parse("[not:an:ip]:22"),
+        Err(AddressError::InvalidIpv6("not:an:ip".to_string()))
+    );
+    assert_eq!(
+        Address::parse("[::1]x"),
+        Err(AddressError::Trailing("x".to_string()))
+    );
+    assert_eq!(
+        Address::parse("fe80::1%"),
+        Err(AddressError::InvalidIpv6("fe80::1%".to_string()))
     );
+}
 
-    assert_eq!(session.get_user_name(), "");
+#[test]
+fn port_test6() {
+    let session = session_to(SESSION_NAME!(), "[::1]:8022", ConnectionType::SSH).unwrap();
+
+    assert_eq!(session.port(), 8022);
+    assert_eq!(
+        Address::parse("[::1]:"),
+        Err(AddressError::InvalidPort("".to_string()))
+    );
+}
+
+#[test]
+fn port_test7() {
+    let session = session_to(SESSION_NAME!(), "192.168.0.1", ConnectionType::Telnet).unwrap();
+
+    assert_eq!(session.port(), 23);
+}
+
+#[test]
+fn ssh_options_test() {
+    let ssh = SshOptions::parse(
+        "-i \"~/.ssh/my key\" -oStrictHostKeyChecking=no -o User=x -A -X -- tail -f /var/log/syslog",
+    )
+    .unwrap();
+
+    assert_eq!(ssh.identity_file.as_deref(), Some("~/.ssh/my key"));
+    assert_eq!(ssh.options, vec!["StrictHostKeyChecking=no", "User=x"]);
+    assert!(ssh.forward_agent && ssh.forward_x11);
+    assert_eq!(ssh.extra_args, vec!["tail", "-f", "/var/log/syslog"]);
+
+    assert!(SshOptions::parse("-i").is_err());
+    assert!(SshOptions::parse("-o NoValue").is_err());
+    assert!(SshOptions::parse("").unwrap().is_empty());
+}
+
+#[test]
+fn ssh_flags_test() {
+    let mut session = session_to(SESSION_NAME!(), "10.0.0.1", ConnectionType::SSH).unwrap();
+    session.ssh = SshOptions::parse("-C -v -- uptime").unwrap();
+
+    assert_eq!(session.ssh.flags, vec!["-C", "-v"]);
+    assert_eq!(
+        session.connect_args(&[]),
+        vec!["-C", "-v", "-p", "22", "10.0.0.1", "uptime"]
+    );
+
+    // flags keep their values, the first argument that isn't a flag starts the command
+    let ssh = SshOptions::parse("-F ~/.ssh/other -tt -Ecfg.log top -b 1").unwrap();
+    assert_eq!(ssh.flags, vec!["-F", "~/.ssh/other", "-tt", "-Ecfg.log"]);
+    assert_eq!(ssh.extra_args, vec!["top", "-b", "1"]);
+    assert_eq!(SshOptions::parse(&ssh.to_args_string()).unwrap(), ssh);
+
+    assert!(SshOptions::parse("-p 2200").is_err());
+    assert!(SshOptions::parse("-Cl root").is_err());
+    assert!(SshOptions::parse("-F").is_err());
+}
+
+#[test]
+fn ssh_connect_args_test() {
+    let mut session =
+        session_to(SESSION_NAME!(), "root@10.0.0.1:2222", ConnectionType::SSH).unwrap();
+    session.ssh = SshOptions::parse("-i id -o A=b -A uptime").unwrap();
+
+    assert_eq!(
+        session.connect_args(&[]),
+        vec!["-i", "id", "-o", "A=b", "-A", "-p", "2222", "-l", "root", "10.0.0.1", "uptime"]
+    );
+
+    let json = serde_json::to_string(&session).unwrap();
+    assert!(json.contains(r#""ssh":{"identity_file":"id","options":["A=b"],"forward_agent":true,"extra_args":["uptime"]}"#));
+
+    let loaded: Session = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.ssh, session.ssh);
+}
+
+fn jump_config() -> Config {
+    let mut bastion =
+        session_to("bastion".to_string(), "jump@10.0.0.1", ConnectionType::SSH).unwrap();
+    bastion.ssh.jump = vec![JumpHost::parse("edge@[2001:db8::1]:2222").unwrap()];
+
+    let mut db = session_to("db".to_string(), "10.0.1.5", ConnectionType::SSH).unwrap();
+    db.ssh.jump = vec![JumpHost::parse("prod/bastion").unwrap()];
+
+    let mut cfg = Config::new();
+    cfg.session_groups
+        .push(SessionGroup::new("prod".to_string(), vec![bastion, db]));
+    cfg
+}
+
+#[test]
+fn jump_chain_test() {
+    let cfg = jump_config();
+    let db = cfg.find_session("prod/db").unwrap();
+    let chain = cfg.resolve_jump_chain(&db).unwrap();
+
+    assert_eq!(chain, vec!["edge@[2001:db8::1]:2222", "jump@10.0.0.1:22"]);
+    assert_eq!(
+        db.connect_args(&chain),
+        vec![
+            "-J",
+            "edge@[2001:db8::1]:2222,jump@10.0.0.1:22",
+            "-p",
+            "22",
+            "10.0.1.5"
+        ]
+    );
+}
+
+#[test]
+fn jump_cycle_test() {
+    let mut cfg = jump_config();
+    cfg.session_groups[0].sessions[0].ssh.jump = vec![JumpHost::Session("prod/db".to_string())];
+
+    let db = cfg.find_session("prod/db").unwrap();
+    assert_eq!(
+        cfg.resolve_jump_chain(&db),
+        Err("jump host cycle: prod/bastion -> prod/db -> prod/bastion".to_string())
+    );
+
+    cfg.session_groups[0].sessions[1].ssh.jump = vec![JumpHost::Session("prod/nope".to_string())];
+    let db = cfg.find_session("prod/db").unwrap();
+    assert_eq!(
+        cfg.resolve_jump_chain(&db),
+        Err("jump host 'prod/nope' does not exist".to_string())
+    );
+}
+
+#[test]
+fn jump_parse_test() {
+    let ssh = SshOptions::parse("-J eu/prod/bastion,root@10.0.0.1:22").unwrap();
+
+    assert_eq!(
+        ssh.jump,
+        vec![
+            JumpHost::Session("eu/prod/bastion".to_string()),
+            JumpHost::Host("root@10.0.0.1:22".to_string())
+        ]
+    );
+    assert!(SshOptions::parse("-J root@:22").is_err());
+}
+
+#[test]
+fn forward_parse_test() {
+    assert_eq!(
+        Forward::parse('L', "8080:db.internal:5432").unwrap(),
+        Forward::Local {
+            bind_address: None,
+            port: 8080,
+            host: "db.internal".to_string(),
+            host_port: 5432
+        }
+    );
+    assert_eq!(
+        Forward::parse('R', "[::1]:0:[fe80::2]:80").unwrap().spec(),
+        "[::1]:0:[fe80::2]:80"
+    );
+    assert_eq!(
+        Forward::parse('D', "*:1080").unwrap().to_string(),
+        "-D *:1080"
+    );
+}
+
+#[test]
+fn forward_validation_test() {
+    assert!(Forward::parse('L', "0:db:5432").is_err());
+    assert!(Forward::parse('L', "8080:db:65536").is_err());
+    assert!(Forward::parse('L', "8080:db").is_err());
+    assert!(Forward::parse('L', "example.com:8080:db:5432").is_err());
+    assert!(Forward::parse('L', "8080:d b:5432").is_err());
+    assert!(Forward::parse('D', "[::1:1080").is_err());
+    assert!(SshOptions::parse("-L 8080").is_err());
+}
+
+#[test]
+fn forward_connect_args_test() {
+    let mut session = session_to(SESSION_NAME!(), "10.0.0.1", ConnectionType::SSH).unwrap();
+    session.ssh =
+        SshOptions::parse("-L 127.0.0.1:8080:localhost:80 -R9000:localhost:9000 -D 1080").unwrap();
+
+    assert_eq!(
+        session.connect_args(&[]),
+        vec![
+            "-L",
+            "127.0.0.1:8080:localhost:80",
+            "-R",
+            "9000:localhost:9000",
+            "-D",
+            "1080",
+            "-p",
+            "22",
+            "10.0.0.1"
+        ]
+    );
+
+    let loaded: Session = serde_json::from_str(&serde_json::to_string(&session).unwrap()).unwrap();
+    assert_eq!(loaded.ssh.forwards, session.ssh.forwards);
+}
+
+#[test]
+fn tunnel_args_test() {
+    let mut session = session_to(SESSION_NAME!(), "root@10.0.0.1", ConnectionType::SSH).unwrap();
+    assert!(session.tunnel_args(&[]).is_err());
+
+    session.ssh = SshOptions::parse("-L 5432:db:5432 -- uptime").unwrap();
+    assert_eq!(
+        session
+            .tunnel_args(&["jump@10.0.0.2:22".to_string()])
+            .unwrap(),
+        vec![
+            "-N",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "BatchMode=yes",
+            "-L",
+            "5432:db:5432",
+            "-J",
+            "jump@10.0.0.2:22",
+            "-p",
+            "22",
+            "-l",
+            "root",
+            "10.0.0.1"
+        ]
+    );
+}
+
+/// a stand-in for ssh that ignores its arguments and keeps running
+#[cfg(target_family = "unix")]
+fn stub_ssh(dir: &TempDir) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.path("ssh");
+    std::fs::write(&path, "#!/bin/sh\nexec sleep 60\n").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+#[cfg(target_family = "unix")]
+fn tunnel_manager_test() {
+    let dir = TempDir::new("tunnel");
+    let mut tunnels = TunnelManager::with_program(&stub_ssh(&dir));
+
+    let telnet = session_to(SESSION_NAME!(), "10.0.0.1", ConnectionType::Telnet).unwrap();
+    assert!(tunnels.start("g/telnet", &telnet, &[]).is_err());
+    assert!(tunnels.tunnels().is_empty());
+
+    let mut session = session_to(SESSION_NAME!(), "127.0.0.1:1", ConnectionType::SSH).unwrap();
+    session.ssh.forwards = vec![Forward::parse('D', "127.0.0.1:10800").unwrap()];
+    tunnels.start("g/s", &session, &[]).unwrap();
+    tunnels.poll();
+    assert!(tunnels.get("g/s").unwrap().is_running());
+    assert!(tunnels.start("g/s", &session, &[]).is_err());
+
+    // renaming the group keeps the tunnel under the new path
+    tunnels.rename("g", "h");
+    assert!(tunnels.get("g/s").is_none());
+    assert!(tunnels.get("h/s").unwrap().is_running());
+
+    tunnels.stop_all();
+    assert_eq!(tunnels.get("h/s").unwrap().status, TunnelStatus::Stopped);
+
+    // a tunnel that was stopped can be started again
+    tunnels.start("h/s", &session, &[]).unwrap();
+    assert_eq!(tunnels.tunnels().len(), 1);
+    tunnels.stop("h/s");
+
+    // removing a group stops the tunnels of everything in it, but not of a
+    // group that only starts with the same name
+    tunnels.start("h/s", &session, &[]).unwrap();
+    tunnels.start("h/t/s", &session, &[]).unwrap();
+    tunnels.start("hh/s", &session, &[]).unwrap();
+    tunnels.remove("h");
+    assert_eq!(tunnels.tunnels().len(), 1);
+    assert!(tunnels.get("hh/s").unwrap().is_running());
+    tunnels.stop_all();
+
+    let mut missing = TunnelManager::with_program(&dir.path("no-such-ssh"));
+    let err = missing.start("g/s", &session, &[]).unwrap_err();
+    assert!(err.contains("not found"));
+    assert!(missing.tunnels().is_empty());
+}
+
+fn nested_config() -> Config {
+    let ssh =
+        |name: &str, addr: &str| session_to(name.to_string(), addr, ConnectionType::SSH).unwrap();
+
+    let mut prod = SessionGroup::new("prod".to_string(), vec![ssh("db", "10.0.1.1")]);
+    prod.groups.push(SessionGroup::new(
+        "eu".to_string(),
+        vec![ssh("web", "10.0.2.1")],
+    ));
+
+    let mut work = SessionGroup::new("work".to_string(), vec![ssh("bastion", "10.0.0.1")]);
+    work.groups.push(prod);
+
+    let mut cfg = Config::new();
+    cfg.session_groups.push(work);
+    cfg.session_groups.push(SessionGroup::new(
+        "home".to_string(),
+        vec![ssh("nas", "192.168.0.2")],
+    ));
+    cfg
+}
+
+#[test]
+fn nested_groups_walk_test() {
+    let cfg = nested_config();
+    let paths: Vec<String> = cfg.sessions().into_iter().map(|(p, _)| p).collect();
+
+    assert_eq!(
+        paths,
+        vec![
+            "work/bastion",
+            "work/prod/db",
+            "work/prod/eu/web",
+            "home/nas"
+        ]
+    );
+    assert_eq!(cfg.rows().len(), 8);
+    assert_eq!(
+        cfg.find_session("work/prod/eu/web").unwrap().host,
+        "10.0.2.1"
+    );
+    assert!(cfg.find_session("prod/eu/web").is_none());
+}
+
+#[test]
+fn nested_groups_serde_test() {
+    let json = r#"{"name":"flat","sessions":[]}"#;
+    let sg: SessionGroup = serde_json::from_str(json).unwrap();
+
+    assert!(sg.groups.is_empty());
+    assert_eq!(serde_json::to_string(&sg).unwrap(), json);
+
+    let cfg = nested_config();
+    let json = serde_json::to_string(&cfg.session_groups).unwrap();
+    let groups: Vec<SessionGroup> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(groups[0].groups[0].groups[0].name, "eu");
+}
+
+#[test]
+fn nested_groups_remove_test() {
+    let mut cfg = nested_config();
+
+    assert!(cfg.remove_group("work/prod"));
+    assert!(!cfg.remove_group("work/prod"));
+
+    let paths: Vec<String> = cfg.sessions().into_iter().map(|(p, _)| p).collect();
+    assert_eq!(paths, vec!["work/bastion", "home/nas"]);
+}
+
+#[test]
+fn nested_groups_merge_test() {
+    let mut cfg = nested_config();
+    let mut import = nested_config().session_groups.remove(0);
+    import.groups[0].groups[0]
+        .sessions
+        .push(session_to("cache".to_string(), "10.0.2.2", ConnectionType::SSH).unwrap());
+
+    let added = cfg.merge_groups(vec![import]);
+
+    assert_eq!(added.len(), 1);
+    assert!(added[0].sessions.is_empty());
+    assert!(cfg.find_session("work/prod/eu/cache").is_some());
+    assert_eq!(cfg.sessions().len(), 5);
+}
+
+#[test]
+fn group_defaults_test() {
+    let json = r#"{"version":"2","session_groups":[{
+        "name":"prod-eu",
+        "defaults":{"user":"deploy","port":2222,"ssh":{"identity_file":"~/.ssh/prod","options":["ServerAliveInterval=30"],"extra_args":["uptime"]}},
+        "sessions":[
+            {"name":"web","host":"10.0.0.1"},
+            {"name":"console","host":"10.0.0.9","connection_type":"Telnet"},
+            {"name":"db","host":"10.0.0.2","user":"postgres","port":22,"ssh":{"options":["ServerAliveInterval=5"]}}
+        ],
+        "groups":[{
+            "name":"legacy",
+            "defaults":{"connection_type":"Telnet","port":null},
+            "sessions":[{"name":"switch","host":"10.0.0.3"}]
+        }]
+    }]}"#;
+    let cfg: Config = serde_json::from_str(json).unwrap();
+
+    // the remote command of the group isn't run on every session
+    let web = cfg.find_session("prod-eu/web").unwrap();
+    assert_eq!(
+        web.connect_args(&[]),
+        vec![
+            "-i",
+            "~/.ssh/prod",
+            "-o",
+            "ServerAliveInterval=30",
+            "-p",
+            "2222",
+            "-l",
+            "deploy",
+            "10.0.0.1"
+        ]
+    );
+
+    let db = cfg.find_session("prod-eu/db").unwrap();
+    assert_eq!(db.address(), "postgres@10.0.0.2:22");
+    assert_eq!(
+        db.ssh.options,
+        vec!["ServerAliveInterval=5", "ServerAliveInterval=30"]
+    );
+
+    // the ssh port of the group doesn't apply to telnet, a session or child
+    // group that switches the type gets the default port of the new type
+    let console = cfg.find_session("prod-eu/console").unwrap();
+    assert_eq!(console.connect_args(&[]), vec!["10.0.0.9", "23"]);
+
+    let switch = cfg.find_session("prod-eu/legacy/switch").unwrap();
+    assert!(switch.connection_type() == ConnectionType::Telnet);
+    assert_eq!(switch.connect_args(&[]), vec!["10.0.0.3", "23"]);
+
+    // the stored session is left untouched
+    let raw = &cfg.session_groups[0].sessions[0];
+    assert_eq!(raw.port, None);
+    assert_eq!(raw.ssh.identity_file, None);
+}
+
+#[test]
+fn add_session_test() {
+    let mut cfg = nested_config();
+    let session = |name: &str| {
+        Session::builder()
+            .name(name.to_string())
+            .address("10.0.9.9")
+            .unwrap()
+            .build()
+    };
+
+    cfg.add_session("work/prod", session("cache")).unwrap();
+    cfg.add_session("lab/new", session("pi")).unwrap();
+
+    assert!(cfg.find_session("work/prod/cache").is_some());
+    assert!(cfg.find_session("lab/new/pi").is_some());
+    assert_eq!(cfg.session_groups[0].groups.len(), 1);
+    assert_eq!(cfg.session_groups[2].name, "lab");
+
+    // inherits the connection type of the group when none is given
+    assert_eq!(
+        cfg.session_groups[2].groups[0].sessions[0].connection_type,
+        None
+    );
+
+    assert!(cfg.add_session("work/prod", session("cache")).is_err());
+    assert!(cfg.add_session("work//prod", session("x")).is_err());
+    assert!(cfg.add_session("work", session("a/b")).is_err());
+}
+
+#[test]
+fn add_group_test() {
+    let mut cfg = nested_config();
+    let defaults = SessionDefaults {
+        user: Some("ops".to_string()),
+        ..SessionDefaults::default()
+    };
+
+    cfg.add_group("work/staging", defaults.clone()).unwrap();
+    cfg.add_group("lab/pis", SessionDefaults::default())
+        .unwrap();
+
+    assert_eq!(cfg.session_groups[0].groups[1].name, "staging");
+    assert_eq!(cfg.session_groups[0].groups[1].defaults, defaults);
+    assert_eq!(cfg.session_groups[2].groups[0].name, "pis");
+
+    assert!(cfg
+        .add_group("work/prod", SessionDefaults::default())
+        .is_err());
+    assert!(cfg
+        .add_group("work//x", SessionDefaults::default())
+        .is_err());
+}
+
+#[test]
+fn find_node_test() {
+    let mut cfg = nested_config();
+
+    assert_eq!(cfg.find_node("work/prod"), Ok(NodeId::Group(vec![0, 0])));
+    assert_eq!(
+        cfg.find_node("work/prod/eu/web"),
+        Ok(NodeId::Session(vec![0, 0, 0], 0))
+    );
+    assert!(cfg.find_node("work/nope").is_err());
+
+    // a group and a session of the same name can't be told apart
+    assert!(cfg
+        .add_group("work/bastion", SessionDefaults::default())
+        .is_err());
+    cfg.session_groups[0]
+        .groups
+        .push(SessionGroup::new("bastion".to_string(), Vec::new()));
+    assert!(cfg.find_node("work/bastion").is_err());
+}
+
+#[test]
+fn move_node_test() {
+    let mut cfg = nested_config();
+    cfg.session_groups[0].groups[0].sessions[0].ssh.jump =
+        vec![JumpHost::Session("work/bastion".to_string())];
+    cfg.session_groups[1].sessions[0].ssh.jump =
+        vec![JumpHost::Session("work/prod/eu/web".to_string())];
+
+    assert_eq!(
+        cfg.move_node("work/bastion", "home"),
+        Ok("home/bastion".to_string())
+    );
+    assert_eq!(cfg.move_node("work/prod/eu", "/"), Ok("eu".to_string()));
+
+    let paths: Vec<String> = cfg.sessions().into_iter().map(|(p, _)| p).collect();
+    assert_eq!(
+        paths,
+        vec!["work/prod/db", "home/nas", "home/bastion", "eu/web"]
+    );
+
+    // jump hosts follow what they point to
+    assert_eq!(
+        cfg.find_session("work/prod/db").unwrap().ssh.jump,
+        vec![JumpHost::Session("home/bastion".to_string())]
+    );
+    assert_eq!(
+        cfg.find_session("home/nas").unwrap().ssh.jump,
+        vec![JumpHost::Session("eu/web".to_string())]
+    );
+
+    assert!(cfg.move_node("work", "work/prod").is_err());
+    assert!(cfg.move_node("home/nas", "/").is_err());
+    assert!(cfg.move_node("eu", "work//x").is_err());
+
+    cfg.add_group("work/eu", SessionDefaults::default())
+        .unwrap();
+    assert!(cfg.move_node("eu", "work").is_err());
+
+    // a session is never turned into a group as well
+    assert_eq!(
+        cfg.move_node("eu", "home/nas"),
+        Err("'home/nas' is a session, not a group".to_string())
+    );
+    assert!(cfg.move_node("eu", "home/nas/x").is_err());
+    assert!(cfg
+        .add_session(
+            "home/bastion",
+            session_to("a".to_string(), "a", ConnectionType::SSH).unwrap()
+        )
+        .is_err());
+    assert!(cfg
+        .add_group("home/bastion", SessionDefaults::default())
+        .is_err());
+    assert_eq!(
+        cfg.find_node("home/bastion"),
+        Ok(NodeId::Session(vec![1], 1))
+    );
+    // nothing was lost on the errors
+    assert_eq!(cfg.sessions().len(), 4);
+}
+
+#[test]
+fn find_node_duplicates_test() {
+    let mut cfg = nested_config();
+    let nas = cfg.session_groups[1].sessions[0].clone();
+    cfg.session_groups[1].sessions.push(nas);
+    assert!(cfg
+        .find_node("home/nas")
+        .unwrap_err()
+        .starts_with("there are 2 sessions 'home/nas'"));
+
+    let home = cfg.session_groups[1].clone();
+    cfg.session_groups.push(home);
+    assert!(cfg
+        .find_node("home")
+        .unwrap_err()
+        .starts_with("there are 2 groups 'home'"));
+}
+
+#[test]
+fn query_session_test() {
+    let mut cfg = nested_config();
+    cfg.add_session(
+        "home",
+        session_to("db".to_string(), "192.168.0.3", ConnectionType::SSH).unwrap(),
+    )
+    .unwrap();
+
+    let path = |q: &str| cfg.query_session(q).map(|(p, _)| p);
+
+    assert_eq!(path("work/prod/db").unwrap(), "work/prod/db");
+    assert_eq!(path("bastion").unwrap(), "work/bastion");
+    assert_eq!(path("2").unwrap(), "work/prod/eu/web");
+    assert_eq!(path("nas").unwrap(), "home/nas");
+    assert_eq!(path("eu web").unwrap(), "work/prod/eu/web");
+
+    let err = path("db").unwrap_err();
+    assert!(err.contains("work/prod/db") && err.contains("home/db"));
+    assert!(path("99").unwrap_err().contains("index 99"));
+    assert!(path("zzz").is_err());
+}
+
+#[test]
+fn config_load_errors_test() {
+    let dir = TempDir::new("load");
+    let path = |name: &str| dir.path(name);
+
+    assert!(matches!(
+        Config::load(&path("missing.json")),
+        Err(Error::File { .. })
+    ));
+    assert!(Config::load_or_new(&path("missing.json")).is_ok());
+
+    std::fs::write(path("broken.json"), "{").unwrap();
+    assert!(matches!(
+        Config::load(&path("broken.json")),
+        Err(Error::Parse { .. })
+    ));
+    // a broken file is reported, not replaced with an empty config
+    assert!(Config::load_or_new(&path("broken.json")).is_err());
+
+    nested_config().save(&path("saved.json")).unwrap();
+    assert_eq!(
+        Config::load(&path("saved.json")).unwrap().sessions().len(),
+        4
+    );
+    assert!(nested_config().save(&path("missing/saved.json")).is_err());
 }
@@ -0,0 +1,276 @@
+use std::fs;
+use std::path::Path;
+
+use crate::interop::ssh_config::{self, pattern_match};
+use crate::session_core::{
+    connection_type::ConnectionType, forward::Forward, jump_host::JumpHost, session::Session,
+    session_group::SessionGroup,
+};
+use crate::tests::{session_to, TempDir};
+use crate::ui::config::Config;
+
+const SSH_CONFIG: &str = "\
+# global options
+User fallback
+
+Host web1 web2
+    HostName %h.example.com
+    Port 2222
+
+Host db
+    HostName=10.0.0.5
+    User \"postgres\"
+    IdentityFile ~/.ssh/db_key
+    ProxyJump bastion
+
+Host *.internal !skip.internal
+    User internal
+
+Host web*
+    User deploy
+    IdentityFile ~/.ssh/web_key
+
+Match host db
+    User ignored
+";
+
+fn parse(content: &str) -> Vec<ssh_config::SshHost> {
+    ssh_config::parse_str(content, Path::new("config"), Path::new(".")).unwrap()
+}
+
+#[test]
+fn pattern_test() {
+    assert!(pattern_match("*", "anything"));
+    assert!(pattern_match("web?", "web1"));
+    assert!(pattern_match("*.internal", "a.b.internal"));
+    assert!(!pattern_match("web?", "web10"));
+    assert!(!pattern_match("*.internal", "internal"));
+}
+
+#[test]
+fn host_test() {
+    let hosts = parse(SSH_CONFIG);
+    let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+
+    assert_eq!(aliases, vec!["web1", "web2", "db"]);
+
+    assert_eq!(hosts[0].host_name.as_deref(), Some("web1.example.com"));
+    assert_eq!(hosts[0].port, Some(2222));
+    // the global `User` comes first, so it wins over the `web*` block
+    assert_eq!(hosts[0].user.as_deref(), Some("fallback"));
+    assert_eq!(hosts[0].identity_files, vec!["~/.ssh/web_key"]);
+
+    assert_eq!(hosts[2].host_name.as_deref(), Some("10.0.0.5"));
+    assert_eq!(hosts[2].proxy_jump.as_deref(), Some("bastion"));
+    assert_eq!(hosts[2].port, None);
+}
+
+#[test]
+fn wildcard_test() {
+    let hosts = parse(
+        "Host a.internal skip.internal\n\
+         Host *.internal !skip.internal\n    User internal\n",
+    );
+
+    assert_eq!(hosts[0].user.as_deref(), Some("internal"));
+    assert_eq!(hosts[1].user, None);
+}
+
+#[test]
+fn host_name_tokens_test() {
+    let hosts = parse("Host a\n    HostName %h.lan%%h\nHost b\n    HostName fe80::1%%eth0\n");
+
+    assert_eq!(hosts[0].host_name.as_deref(), Some("a.lan%h"));
+    assert_eq!(hosts[1].host_name.as_deref(), Some("fe80::1%eth0"));
+}
+
+#[test]
+fn include_test() {
+    let dir = TempDir::new("ssh_include");
+    fs::create_dir_all(dir.join("conf.d")).unwrap();
+    fs::write(
+        dir.join("conf.d/work.conf"),
+        "Host work\n    HostName work.example.com\n",
+    )
+    .unwrap();
+    fs::write(dir.join("conf.d/home.conf"), "Host home\n    Port 22022\n").unwrap();
+
+    // relative includes are found next to the imported file, not in ~/.ssh
+    let root = dir.join("config");
+    fs::write(&root, "Include conf.d/*.conf\nHost main\n").unwrap();
+    let hosts = ssh_config::parse_file(&root).unwrap();
+    let groups = ssh_config::to_session_groups(&hosts, &root);
+
+    let names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["home.conf", "work.conf", ssh_config::DEFAULT_GROUP_NAME]
+    );
+    assert_eq!(groups[0].sessions[0].port(), 22022);
+    assert_eq!(groups[1].sessions[0].host, "work.example.com");
+}
+
+#[test]
+fn dedup_test() {
+    let mut cfg = Config::new();
+    cfg.session_groups.push(SessionGroup::new(
+        "existing".to_string(),
+        vec![session_to("a".to_string(), "root@10.0.0.5", ConnectionType::SSH).unwrap()],
+    ));
+
+    let hosts = parse("Host a b\n    HostName 10.0.0.5\n    User root\nHost c\n");
+    let groups = ssh_config::to_session_groups(&hosts, Path::new("config"));
+
+    let preview = cfg.dedup_groups(groups.clone());
+    assert_eq!(preview[0].sessions.len(), 1);
+    assert_eq!(preview[0].sessions[0].name, "c");
+    assert_eq!(cfg.session_groups.len(), 1);
+
+    cfg.merge_groups(groups.clone());
+    assert_eq!(cfg.session_groups.len(), 2);
+
+    // importing again adds nothing
+    assert!(cfg.merge_groups(groups).is_empty());
+}
+
+#[test]
+fn dedup_jump_test() {
+    let mut cfg = Config::new();
+    cfg.session_groups.push(SessionGroup::new(
+        "infra".to_string(),
+        vec![session_to("bastion".to_string(), "10.0.0.1", ConnectionType::SSH).unwrap()],
+    ));
+
+    let hosts = parse(
+        "Host bastion\n    HostName 10.0.0.1\nHost web\n    HostName 10.0.0.5\n    ProxyJump bastion\n",
+    );
+    let groups = ssh_config::to_session_groups(&hosts, Path::new("config"));
+    assert_eq!(
+        groups[0].sessions[1].ssh.jump,
+        vec![JumpHost::Session(format!(
+            "{}/bastion",
+            ssh_config::DEFAULT_GROUP_NAME
+        ))]
+    );
+
+    // the dropped duplicate is replaced by the session already in the config
+    cfg.merge_groups(groups);
+    let path = format!("{}/web", ssh_config::DEFAULT_GROUP_NAME);
+    let web = cfg.find_session(&path).unwrap();
+    assert_eq!(
+        web.ssh.jump,
+        vec![JumpHost::Session("infra/bastion".to_string())]
+    );
+    assert_eq!(cfg.resolve_jump_chain(&web).unwrap(), vec!["10.0.0.1:22"]);
+}
+
+#[test]
+fn export_test() {
+    let mut cfg = Config::new();
+    cfg.session_groups.push(SessionGroup::new(
+        "Prod EU".to_string(),
+        vec![
+            session_to("Web 1".to_string(), "root@10.0.0.1", ConnectionType::SSH).unwrap(),
+            session_to(
+                "web-1".to_string(),
+                "[fe80::1%eth0]:2222",
+                ConnectionType::SSH,
+            )
+            .unwrap(),
+            session_to("switch".to_string(), "10.0.0.2", ConnectionType::Telnet).unwrap(),
+        ],
+    ));
+
+    let exported = ssh_config::export(&cfg);
+
+    assert!(exported.contains("# group: Prod EU\n"));
+    assert!(exported.contains("# skipped telnet session 'switch' (10.0.0.2:23)\n"));
+    assert!(exported.contains("    HostName fe80::1%%eth0\n"));
+
+    // exported aliases can be imported again
+    let hosts = parse(&exported);
+    let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+    assert_eq!(aliases, vec!["prod-eu-web-1", "prod-eu-web-1-2"]);
+
+    let sessions: Vec<Session> = hosts.iter().map(|h| h.to_session()).collect();
+    assert!(sessions[0].same_target(&cfg.session_groups[0].sessions[0]));
+    assert!(sessions[1].same_target(&cfg.session_groups[0].sessions[1]));
+}
+
+#[test]
+fn export_control_chars_test() {
+    let mut cfg = Config::new();
+    cfg.session_groups.push(SessionGroup::new(
+        "evil\nHost *\n    ProxyCommand sh -c id".to_string(),
+        vec![
+            session_to("web".to_string(), "10.0.0.1", ConnectionType::SSH).unwrap(),
+            session_to(
+                "sw\r\nProxyCommand sh".to_string(),
+                "10.0.0.2",
+                ConnectionType::Telnet,
+            )
+            .unwrap(),
+        ],
+    ));
+
+    let exported = ssh_config::export(&cfg);
+
+    assert!(exported.contains("# group: evil?Host *?    ProxyCommand sh -c id\n"));
+    assert!(exported.contains("# skipped telnet session 'sw??ProxyCommand sh'"));
+    assert!(!exported
+        .lines()
+        .any(|l| l.trim_start().starts_with("ProxyCommand")));
+    assert_eq!(parse(&exported).len(), 1);
+}
+
+#[test]
+fn proxy_jump_test() {
+    let hosts = parse(
+        "Host bastion\n    HostName 10.0.0.1\n\
+         Host db\n    ProxyJump bastion,admin@edge:2222\n\
+         Host web\n    ProxyJump none\n",
+    );
+    let mut cfg = Config::new();
+    cfg.session_groups = ssh_config::to_session_groups(&hosts, Path::new("config"));
+
+    let db = cfg.find_session("ssh_config/db").unwrap();
+    assert_eq!(
+        db.ssh.jump,
+        vec![
+            JumpHost::Session("ssh_config/bastion".to_string()),
+            JumpHost::Host("admin@edge:2222".to_string())
+        ]
+    );
+    assert!(cfg
+        .find_session("ssh_config/web")
+        .unwrap()
+        .ssh
+        .jump
+        .is_empty());
+
+    let exported = ssh_config::export(&cfg);
+    assert!(exported.contains("    ProxyJump 10.0.0.1:22,admin@edge:2222\n"));
+}
+
+#[test]
+fn forward_test() {
+    let hosts =
+        parse("Host db\n    LocalForward 127.0.0.1:5432 localhost:5432\n    DynamicForward 1080\n");
+    let session = hosts[0].to_session();
+
+    assert_eq!(
+        session.ssh.forwards,
+        vec![
+            Forward::parse('L', "127.0.0.1:5432:localhost:5432").unwrap(),
+            Forward::parse('D', "1080").unwrap()
+        ]
+    );
+
+    let mut cfg = Config::new();
+    cfg.session_groups
+        .push(SessionGroup::new("g".to_string(), vec![session]));
+    let exported = ssh_config::export(&cfg);
+
+    assert!(exported.contains("    LocalForward 127.0.0.1:5432 localhost:5432\n"));
+    assert!(exported.contains("    DynamicForward 1080\n"));
+}
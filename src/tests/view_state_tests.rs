@@ -0,0 +1,142 @@
+use std::fs::{self, File};
+use std::time::{Duration, SystemTime};
+
+use crate::tests::{session, TempDir};
+use crate::ui::{
+    config::Config,
+    view_state::{Filter, ViewState},
+};
+
+/// writes `contents` like another program would, with a modification time
+/// that is surely different from the last one
+fn write_raw(path: &str, contents: &str, secs_later: u64) {
+    fs::write(path, contents).unwrap();
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000 + secs_later))
+        .unwrap();
+}
+
+fn write_external(path: &str, cfg: &Config, secs_later: u64) {
+    write_raw(path, &serde_json::to_string(cfg).unwrap(), secs_later);
+}
+
+#[test]
+fn watch_reload_test() {
+    let dir = TempDir::new("watch");
+    let path = dir.path("config.json");
+
+    let mut cfg = Config::new();
+    cfg.add_session("work", session("bastion", "10.0.0.1"))
+        .unwrap();
+    cfg.add_session("work", session("db", "10.0.0.2")).unwrap();
+    cfg.save(&path).unwrap();
+
+    let mut state = ViewState::new(Config::load(&path).unwrap(), path.clone());
+    state.table_state.select(Some(2));
+    assert_eq!(state.selected_row().unwrap().path, "work/db");
+
+    // nothing changed, nothing happens
+    state.watch();
+    assert_eq!(state.config, cfg);
+
+    // rows are added in front of the selected session, it stays selected
+    let mut theirs = cfg.clone();
+    theirs
+        .add_session("lab", session("pi", "10.0.9.9"))
+        .unwrap();
+    theirs.session_groups.rotate_right(1);
+    write_external(&path, &theirs, 10);
+
+    state.watch();
+    assert_eq!(state.config, theirs);
+    assert_eq!(state.selected_row().unwrap().path, "work/db");
+    assert!(!state.changed_on_disk());
+
+    // own changes are never replaced
+    state.config.add_group("mine", Default::default()).unwrap();
+    write_external(&path, &cfg, 20);
+
+    state.watch();
+    assert!(state.config.find_node("mine").is_ok());
+    assert!(state.changed_on_disk());
+}
+
+#[test]
+fn watch_partial_write_test() {
+    let dir = TempDir::new("watch_partial");
+    let path = dir.path("config.json");
+
+    let mut cfg = Config::new();
+    cfg.add_session("work", session("bastion", "10.0.0.1"))
+        .unwrap();
+    cfg.save(&path).unwrap();
+    let mut state = ViewState::new(Config::load(&path).unwrap(), path.clone());
+
+    // the file is read while it is written, and finished within the same
+    // modification time
+    let mut theirs = cfg.clone();
+    theirs.add_group("lab", Default::default()).unwrap();
+    let json = serde_json::to_string(&theirs).unwrap();
+    write_raw(&path, &json[..json.len() / 2], 10);
+
+    state.watch();
+    assert_eq!(state.config, cfg);
+
+    write_raw(&path, &json, 10);
+    state.watch();
+    assert_eq!(state.config, theirs);
+}
+
+#[test]
+fn filter_rows_test() {
+    let mut cfg = Config::new();
+    cfg.add_session("work/eu", session("bastion", "10.0.0.1"))
+        .unwrap();
+    cfg.add_session("lab", session("pi", "10.0.9.9")).unwrap();
+
+    let mut state = ViewState::new(cfg, String::new());
+    let paths = |state: &ViewState| -> Vec<String> {
+        state.visible_rows().into_iter().map(|r| r.path).collect()
+    };
+    assert_eq!(paths(&state).len(), 5);
+
+    state.filter = Some(Filter {
+        query: "bast".to_string(),
+        editing: true,
+    });
+    assert_eq!(paths(&state), ["work", "work/eu", "work/eu/bastion"]);
+
+    // the matches follow changes of the config and of the query
+    state
+        .config
+        .add_session("lab", session("bastion2", "10.0.9.8"))
+        .unwrap();
+    assert_eq!(
+        paths(&state),
+        ["work", "work/eu", "work/eu/bastion", "lab", "lab/bastion2"]
+    );
+
+    state.filter.as_mut().unwrap().query = "pi".to_string();
+    assert_eq!(paths(&state), ["lab", "lab/pi"]);
+}
+
+#[test]
+fn read_only_yaml_test() {
+    let dir = TempDir::new("read_only");
+    let path = dir.path("config.yaml");
+    let yaml = "version: 2.1.0\nsession_groups:\n# the office\n- name: work\n  sessions: []\n";
+    fs::write(&path, yaml).unwrap();
+
+    // known before anything is edited
+    let mut state = ViewState::new(Config::load(&path).unwrap(), path.clone());
+    assert!(state.read_only.as_ref().unwrap().contains("comments"));
+
+    // without the comments it can be saved again
+    fs::write(&path, yaml.replace("# the office\n", "")).unwrap();
+    state.reload();
+    assert_eq!(state.read_only, None);
+    assert!(Config::check_writable(&path).is_ok());
+}
@@ -1,4 +1,5 @@
 pub mod config;
+pub mod edit_state;
 mod popup_state;
 pub mod view;
-mod view_state;
+pub mod view_state;
@@ -1,13 +1,91 @@
+use std::{fs, io, path::Path};
+
 use serde::{Deserialize, Serialize};
 
-use crate::session_core::session_group::SessionGroup;
+use crate::backup;
+use crate::config_format::{has_yaml_comments, ConfigFormat};
+use crate::error::{self, Error};
+use crate::fuzzy;
+use crate::migrations;
+use crate::session_core::{
+    connection_type::ConnectionType,
+    jump_host::JumpHost,
+    session::Session,
+    session_defaults::SessionDefaults,
+    session_group::{join_path, SessionGroup},
+};
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Config {
+    /// version of gcoma that last saved the config
     pub version: String,
     pub session_groups: Vec<SessionGroup>,
 }
 
+/// Position of a group or session in the group tree, the `Vec` holds the
+/// index of each group from the top level down
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeId {
+    Group(Vec<usize>),
+    Session(Vec<usize>, usize),
+}
+
+/// One line of the flattened group tree
+pub struct TreeRow {
+    pub id: NodeId,
+    pub depth: usize,
+    /// `group/.../name` path of the row
+    pub path: String,
+}
+
+/// points the jump hosts in `sg` (and its children) that reference `old`, or
+/// something in it, to `new`
+fn rename_jumps(sg: &mut SessionGroup, old: &str, new: &str) {
+    let jumps = sg
+        .sessions
+        .iter_mut()
+        .flat_map(|s| s.ssh.jump.iter_mut())
+        .chain(sg.defaults.ssh.jump.iter_mut());
+
+    for jump in jumps {
+        if let JumpHost::Session(path) = jump {
+            if path == old {
+                *path = new.to_string();
+            } else if let Some(rest) = path.strip_prefix(&format!("{}/", old)) {
+                *path = format!("{}/{}", new, rest);
+            }
+        }
+    }
+
+    for g in sg.groups.iter_mut() {
+        rename_jumps(g, old, new);
+    }
+}
+
+fn push_rows(groups: &[SessionGroup], parent: &[usize], prefix: &str, out: &mut Vec<TreeRow>) {
+    for (i, sg) in groups.iter().enumerate() {
+        let mut idx = parent.to_vec();
+        idx.push(i);
+        let path = join_path(prefix, &sg.name);
+
+        out.push(TreeRow {
+            id: NodeId::Group(idx.clone()),
+            depth: parent.len(),
+            path: path.clone(),
+        });
+
+        for (j, s) in sg.sessions.iter().enumerate() {
+            out.push(TreeRow {
+                id: NodeId::Session(idx.clone(), j),
+                depth: idx.len(),
+                path: join_path(&path, &s.name),
+            });
+        }
+
+        push_rows(&sg.groups, &idx, &path, out);
+    }
+}
+
 impl Config {
     pub fn new() -> Config {
         Config {
@@ -16,11 +94,522 @@ impl Config {
         }
     }
 
-    pub fn save(&self, path: &str) {
+    /// reads the config at `path` in the format of its extension, configs
+    /// saved by older versions of gcoma are upgraded to the current layout
+    pub fn load(path: &str) -> error::Result<Config> {
+        Config::load_as(path, ConfigFormat::of(Path::new(path)))
+    }
+
+    pub fn load_as(path: &str, format: ConfigFormat) -> error::Result<Config> {
+        let cfg_str = fs::read_to_string(path).map_err(|e| Error::file(path, e))?;
+        let parse_error = |source| Error::Parse {
+            path: path.to_string(),
+            source,
+        };
+
+        let mut value = format.parse(&cfg_str).map_err(parse_error)?;
+        migrations::migrate(&mut value, env!("CARGO_PKG_VERSION")).map_err(|source| {
+            Error::Migration {
+                path: path.to_string(),
+                source,
+            }
+        })?;
+
+        serde_json::from_value(value).map_err(|e| parse_error(e.into()))
+    }
+
+    /// like `load`, but a file that doesn't exist yet is an empty config
+    pub fn load_or_new(path: &str) -> error::Result<Config> {
+        match Config::load(path) {
+            Err(Error::File { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                Ok(Config::new())
+            }
+            loaded => loaded,
+        }
+    }
+
+    /// the config as written by `save` over the file `current`, stamped with
+    /// the current version
+    pub fn to_string_as(
+        &self,
+        format: ConfigFormat,
+        current: Option<&str>,
+    ) -> error::Result<String> {
         let mut data: Config = self.clone();
         data.version = env!("CARGO_PKG_VERSION").to_string();
 
-        let cfg_str = serde_json::to_string_pretty(&data).unwrap();
-        std::fs::write(path, cfg_str).unwrap();
+        format
+            .serialize_over(&data, current)
+            .map_err(|e| Error::Io(io::Error::other(e)))
+    }
+
+    /// fails if `save` would refuse to replace the file at `path`, which is
+    /// the case for a yaml file with comments
+    pub fn check_writable(path: &str) -> error::Result<()> {
+        if ConfigFormat::of(Path::new(path)) != ConfigFormat::Yaml {
+            return Ok(());
+        }
+
+        match fs::read_to_string(path) {
+            Ok(current) if has_yaml_comments(&current) => Err(Error::Invalid(format!(
+                "{} has comments that saving would remove, remove them or convert the config to TOML, which keeps them",
+                path
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// replaces the file at `path` atomically in the format of its extension,
+    /// the previous file is kept as a backup, nothing is written if the file
+    /// wouldn't change
+    ///
+    /// comments in a toml file are kept, a yaml file with comments isn't
+    /// written at all as they would be lost
+    pub fn save(&self, path: &str) -> error::Result<()> {
+        Config::check_writable(path)?;
+        let format = ConfigFormat::of(Path::new(path));
+        let current = fs::read_to_string(path).ok();
+
+        let cfg_str = self.to_string_as(format, current.as_deref())?;
+        if current.is_some_and(|current| current == cfg_str) {
+            return Ok(());
+        }
+
+        let path = Path::new(path);
+        backup::create(path).map_err(|e| Error::file(path, e))?;
+        backup::write_atomic(path, &cfg_str).map_err(|e| Error::file(path, e))
+    }
+
+    /// every group and session in display order
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        push_rows(&self.session_groups, &[], "", &mut rows);
+        rows
+    }
+
+    /// every session with its `group/.../session` path, in display order, with
+    /// the defaults of their groups applied
+    pub fn sessions(&self) -> Vec<(String, Session)> {
+        let mut sessions = Vec::new();
+
+        for sg in self.session_groups.iter() {
+            sg.walk_sessions("", &SessionDefaults::default(), &mut sessions);
+        }
+
+        sessions
+    }
+
+    pub fn group(&self, idx: &[usize]) -> Option<&SessionGroup> {
+        let (first, rest) = idx.split_first()?;
+        let mut group = self.session_groups.get(*first)?;
+
+        for i in rest.iter() {
+            group = group.groups.get(*i)?;
+        }
+
+        Some(group)
+    }
+
+    pub fn group_mut(&mut self, idx: &[usize]) -> Option<&mut SessionGroup> {
+        let (first, rest) = idx.split_first()?;
+        let mut group = self.session_groups.get_mut(*first)?;
+
+        for i in rest.iter() {
+            group = group.groups.get_mut(*i)?;
+        }
+
+        Some(group)
+    }
+
+    /// the session with the defaults of its groups applied, this is what is
+    /// shown and connected to
+    pub fn effective_session(&self, id: &NodeId) -> Option<Session> {
+        let (idx, i) = match id {
+            NodeId::Session(idx, i) => (idx, *i),
+            NodeId::Group(_) => return None,
+        };
+
+        let mut defaults = SessionDefaults::default();
+        for depth in 1..=idx.len() {
+            defaults = defaults.merge(&self.group(&idx[..depth])?.defaults);
+        }
+
+        Some(self.group(idx)?.sessions.get(i)?.inherit(&defaults))
+    }
+
+    /// fails if a group at the `group/.../group` path can't be created, because
+    /// it or one of its parents would have the path of a session
+    fn check_group_path(&self, path: &str) -> Result<(), String> {
+        if path.split('/').any(|n| n.is_empty()) {
+            return Err(format!("'{}' is not a valid group path", path));
+        }
+
+        let taken = self.rows().into_iter().find(|r| {
+            matches!(r.id, NodeId::Session(..))
+                && (r.path == path || path.starts_with(&format!("{}/", r.path)))
+        });
+        match taken {
+            Some(r) => Err(format!("'{}' is a session, not a group", r.path)),
+            None => Ok(()),
+        }
+    }
+
+    /// the group at the `group/.../group` path, missing groups are created
+    fn create_group_path(&mut self, path: &str) -> Result<&mut SessionGroup, String> {
+        fn find_or_push<'a>(groups: &'a mut Vec<SessionGroup>, name: &str) -> &'a mut SessionGroup {
+            match groups.iter().position(|g| g.name == name) {
+                Some(i) => &mut groups[i],
+                None => {
+                    groups.push(SessionGroup::new(name.to_string(), Vec::new()));
+                    groups.last_mut().unwrap()
+                }
+            }
+        }
+
+        self.check_group_path(path)?;
+        let names: Vec<&str> = path.split('/').collect();
+
+        let mut sg = find_or_push(&mut self.session_groups, names[0]);
+        for name in names[1..].iter() {
+            sg = find_or_push(&mut sg.groups, name);
+        }
+
+        Ok(sg)
+    }
+
+    /// adds `session` to the group at `group_path`, missing groups are created
+    pub fn add_session(&mut self, group_path: &str, session: Session) -> Result<(), String> {
+        if session.name.is_empty() || session.name.contains('/') {
+            return Err(format!("'{}' is not a valid session name", session.name));
+        }
+
+        let sg = self.create_group_path(group_path)?;
+
+        if sg.sessions.iter().any(|s| s.name == session.name) {
+            return Err(format!(
+                "session '{}' already exists in '{}'",
+                session.name, group_path
+            ));
+        }
+
+        sg.sessions.push(session);
+        Ok(())
+    }
+
+    /// adds an empty group at the `group/.../group` path, missing parent
+    /// groups are created
+    pub fn add_group(&mut self, path: &str, defaults: SessionDefaults) -> Result<(), String> {
+        if self.find_node(path).is_ok() {
+            return Err(format!("'{}' already exists", path));
+        }
+
+        self.create_group_path(path)?.defaults = defaults;
+        Ok(())
+    }
+
+    /// the group or session at the `group/.../name` path
+    pub fn find_node(&self, path: &str) -> Result<NodeId, String> {
+        let path = path.trim_matches('/');
+        let found: Vec<NodeId> = self
+            .rows()
+            .into_iter()
+            .filter(|r| r.path == path)
+            .map(|r| r.id)
+            .collect();
+
+        match found.as_slice() {
+            [] => Err(format!("there is no group or session '{}'", path)),
+            [id] => Ok(id.clone()),
+            ids => {
+                let sessions = ids
+                    .iter()
+                    .filter(|id| matches!(id, NodeId::Session(..)))
+                    .count();
+                let what = match (sessions, ids.len() - sessions) {
+                    (n, 0) => format!("there are {} sessions '{}'", n, path),
+                    (0, n) => format!("there are {} groups '{}'", n, path),
+                    _ => format!("'{}' is both a group and a session", path),
+                };
+                Err(format!("{}, rename one of them in the config file", what))
+            }
+        }
+    }
+
+    /// moves the group or session at `path` into the group at `group_path`,
+    /// an empty `group_path` moves a group to the top level, missing groups
+    /// are created and jump hosts are updated, returns the new path
+    pub fn move_node(&mut self, path: &str, group_path: &str) -> Result<String, String> {
+        let path = path.trim_matches('/');
+        let group_path = group_path.trim_matches('/');
+        let id = self.find_node(path)?;
+
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+        let new_path = join_path(group_path, name);
+
+        if parent == group_path {
+            return Ok(new_path);
+        }
+        if let NodeId::Group(_) = id {
+            if group_path == path || group_path.starts_with(&format!("{}/", path)) {
+                return Err(format!("can't move '{}' into itself", path));
+            }
+        } else if group_path.is_empty() {
+            return Err("sessions have to be in a group".to_string());
+        }
+        if !group_path.is_empty() {
+            self.check_group_path(group_path)?;
+        }
+        if self.rows().iter().any(|r| r.path == new_path) {
+            return Err(format!("'{}' already exists", new_path));
+        }
+
+        match &id {
+            NodeId::Session(idx, i) => {
+                let session =
+                    self.group(idx).ok_or("session no longer exists")?.sessions[*i].clone();
+                self.remove(&id);
+                self.create_group_path(group_path)?.sessions.push(session);
+            }
+            NodeId::Group(idx) => {
+                let sg = self.group(idx).ok_or("group no longer exists")?.clone();
+                self.remove(&id);
+
+                match group_path.is_empty() {
+                    true => self.session_groups.push(sg),
+                    false => self.create_group_path(group_path)?.groups.push(sg),
+                }
+            }
+        }
+
+        self.rename_references(path, &new_path);
+        Ok(new_path)
+    }
+
+    /// removes a group (with its children) or a session
+    pub fn remove(&mut self, id: &NodeId) {
+        match id {
+            NodeId::Session(idx, i) => {
+                if let Some(sg) = self.group_mut(idx) {
+                    if *i < sg.sessions.len() {
+                        sg.sessions.remove(*i);
+                    }
+                }
+            }
+            NodeId::Group(idx) => {
+                let (last, parent) = match idx.split_last() {
+                    Some(s) => s,
+                    None => return,
+                };
+
+                let siblings = match parent.is_empty() {
+                    true => &mut self.session_groups,
+                    false => match self.group_mut(parent) {
+                        Some(sg) => &mut sg.groups,
+                        None => return,
+                    },
+                };
+
+                if *last < siblings.len() {
+                    siblings.remove(*last);
+                }
+            }
+        }
+    }
+
+    /// removes the group at the `group/.../group` path, returns false if there is none
+    pub fn remove_group(&mut self, path: &str) -> bool {
+        let id = self
+            .rows()
+            .into_iter()
+            .find(|r| matches!(r.id, NodeId::Group(_)) && r.path == path)
+            .map(|r| r.id);
+
+        match id {
+            Some(id) => {
+                self.remove(&id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// points jump hosts that reference `old` (or something in it) to `new`
+    /// after a session or group was renamed
+    pub fn rename_references(&mut self, old: &str, new: &str) {
+        if old == new {
+            return;
+        }
+
+        for sg in self.session_groups.iter_mut() {
+            rename_jumps(sg, old, new);
+        }
+    }
+
+    /// looks up a session by its `group/.../session` path
+    pub fn find_session(&self, path: &str) -> Option<Session> {
+        self.sessions()
+            .into_iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, s)| s)
+    }
+
+    /// finds the session `query` names, tried in order as a `group/.../session`
+    /// path, a session name, an index of `sessions()` and a fuzzy query,
+    /// returns the path and the session
+    pub fn query_session(&self, query: &str) -> Result<(String, Session), String> {
+        let query = query.trim();
+        let sessions = self.sessions();
+
+        let ambiguous = |candidates: Vec<&(String, Session)>| {
+            let mut msg = format!("'{}' matches more than one session:", query);
+            for (path, s) in candidates.iter() {
+                msg.push_str(&format!("\n  {} ({})", path, s.address()));
+            }
+            msg
+        };
+
+        if let Some(found) = sessions.iter().find(|(p, _)| p == query) {
+            return Ok(found.clone());
+        }
+
+        let named: Vec<&(String, Session)> =
+            sessions.iter().filter(|(_, s)| s.name == query).collect();
+        match named.len() {
+            0 => {}
+            1 => return Ok(named[0].clone()),
+            _ => return Err(ambiguous(named)),
+        }
+
+        if let Ok(idx) = query.parse::<usize>() {
+            return match sessions.get(idx) {
+                Some(found) => Ok(found.clone()),
+                None => Err(format!(
+                    "there is no session with index {}, there are {}",
+                    idx,
+                    sessions.len()
+                )),
+            };
+        }
+
+        let mut scored: Vec<(i64, &(String, Session))> = sessions
+            .iter()
+            .filter_map(|found| Some((fuzzy::score_session(query, &found.0, &found.1)?, found)))
+            .collect();
+        // stable, so equal scores keep the listing order
+        scored.sort_by_key(|(score, _)| -score);
+
+        match scored.as_slice() {
+            [] => Err(format!("no session matches '{}'", query)),
+            [(_, found)] => Ok((*found).clone()),
+            [(best, found), (second, _), ..] if best > second => Ok((*found).clone()),
+            [(best, _), ..] => Err(ambiguous(
+                scored
+                    .iter()
+                    .filter(|(score, _)| score == best)
+                    .map(|(_, found)| *found)
+                    .collect(),
+            )),
+        }
+    }
+
+    /// resolves the jump hosts of `session` into `-J` destinations, jump
+    /// hosts of referenced sessions are put in front of them
+    pub fn resolve_jump_chain(&self, session: &Session) -> Result<Vec<String>, String> {
+        let mut chain = Vec::new();
+        self.resolve_jumps(&session.ssh.jump, &mut Vec::new(), &mut chain)?;
+        Ok(chain)
+    }
+
+    fn resolve_jumps(
+        &self,
+        jumps: &[JumpHost],
+        visiting: &mut Vec<String>,
+        chain: &mut Vec<String>,
+    ) -> Result<(), String> {
+        for jump in jumps.iter() {
+            let path = match jump {
+                JumpHost::Host(addr) => {
+                    chain.push(addr.clone());
+                    continue;
+                }
+                JumpHost::Session(path) => path,
+            };
+
+            if visiting.contains(path) {
+                visiting.push(path.clone());
+                return Err(format!("jump host cycle: {}", visiting.join(" -> ")));
+            }
+
+            let jump_session = self
+                .find_session(path)
+                .ok_or(format!("jump host '{}' does not exist", path))?;
+
+            if jump_session.connection_type() != ConnectionType::SSH {
+                return Err(format!("jump host '{}' is not an ssh session", path));
+            }
+
+            visiting.push(path.clone());
+            self.resolve_jumps(&jump_session.ssh.jump, visiting, chain)?;
+            visiting.pop();
+
+            chain.push(jump_session.address());
+        }
+
+        Ok(())
+    }
+
+    /// drops the sessions of `groups` that are already in the config (or
+    /// repeated within `groups`), empty groups are dropped too. Jump hosts
+    /// that referenced a dropped session point to the one it duplicates
+    pub fn dedup_groups(&self, groups: Vec<SessionGroup>) -> Vec<SessionGroup> {
+        let mut known = self.sessions();
+        // (path of a dropped session, path of the session it duplicates)
+        let mut dropped: Vec<(String, String)> = Vec::new();
+        let mut result = Vec::new();
+
+        for mut sg in groups.into_iter() {
+            sg.retain_sessions(
+                "",
+                &mut |path, s| match known.iter().find(|(_, o)| o.same_target(s)) {
+                    Some((same, _)) => {
+                        dropped.push((path.to_string(), same.clone()));
+                        false
+                    }
+                    None => {
+                        known.push((path.to_string(), s.clone()));
+                        true
+                    }
+                },
+            );
+            sg.prune();
+
+            if !sg.is_empty() {
+                result.push(sg);
+            }
+        }
+
+        for (old, new) in dropped.iter() {
+            for sg in result.iter_mut() {
+                rename_jumps(sg, old, new);
+            }
+        }
+
+        result
+    }
+
+    /// adds the new sessions of `groups`, groups that already exist are
+    /// merged into, returns what was added
+    pub fn merge_groups(&mut self, groups: Vec<SessionGroup>) -> Vec<SessionGroup> {
+        let added = self.dedup_groups(groups);
+
+        for sg in added.iter() {
+            match self.session_groups.iter_mut().find(|g| g.name == sg.name) {
+                Some(g) => g.merge(sg.clone()),
+                None => self.session_groups.push(sg.clone()),
+            }
+        }
+
+        added
     }
 }
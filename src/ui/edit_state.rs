@@ -0,0 +1,254 @@
+use ratatui::style::Style;
+use tui_textarea::{CursorMove, TextArea};
+
+use super::config::{Config, NodeId};
+use crate::session_core::{
+    address::{self, Address},
+    connection_type::ConnectionType,
+    session::Session,
+    session_defaults::SessionDefaults,
+    session_group::join_path,
+    ssh_options::SshOptions,
+};
+
+/// One labeled input of the edit form
+pub struct EditField<'a> {
+    pub label: &'static str,
+    pub textarea: TextArea<'a>,
+}
+
+impl<'a> EditField<'a> {
+    fn new(label: &'static str, placeholder: &'static str, value: String) -> EditField<'a> {
+        let mut textarea = TextArea::new(vec![value]);
+        textarea.set_cursor_line_style(Style::default());
+        textarea.set_placeholder_text(placeholder);
+        textarea.move_cursor(CursorMove::End);
+
+        EditField { label, textarea }
+    }
+
+    pub fn value(&self) -> &str {
+        self.textarea.lines()[0].trim()
+    }
+}
+
+/// Form to change a session or a group in place, opened pre-filled with its
+/// current values
+pub struct EditState<'a> {
+    target: NodeId,
+    /// `group/.../name` path the target had when the form was opened
+    path: String,
+    pub fields: Vec<EditField<'a>>,
+    pub focus: usize,
+    /// validation error of the last save
+    pub error: Option<String>,
+}
+
+const NAME: usize = 0;
+const TYPE: usize = 1;
+const ADDRESS: usize = 2;
+const SSH_OPTIONS: usize = 3;
+const TAGS: usize = 4;
+
+const GROUP_USER: usize = 2;
+const GROUP_PORT: usize = 3;
+const GROUP_SSH_OPTIONS: usize = 4;
+
+fn connection_type_name(connection_type: &Option<ConnectionType>) -> String {
+    match connection_type {
+        Some(ConnectionType::SSH) => "ssh".to_string(),
+        Some(ConnectionType::Telnet) => "telnet".to_string(),
+        None => "".to_string(),
+    }
+}
+
+fn parse_connection_type(s: &str) -> Result<Option<ConnectionType>, String> {
+    match s.to_lowercase().as_str() {
+        "" => Ok(None),
+        "ssh" => Ok(Some(ConnectionType::SSH)),
+        "telnet" => Ok(Some(ConnectionType::Telnet)),
+        _ => Err(format!("'{}' is not 'ssh' or 'telnet'", s)),
+    }
+}
+
+fn parse_name(s: &str) -> Result<String, String> {
+    match s {
+        "" => Err("name is empty".to_string()),
+        s if s.contains('/') => Err(format!("name '{}' can't contain '/'", s)),
+        s => Ok(s.to_string()),
+    }
+}
+
+impl<'a> EditState<'a> {
+    /// opens the form for the group or session at `target`
+    pub fn new(cfg: &Config, target: NodeId) -> Option<EditState<'a>> {
+        let path = cfg.rows().into_iter().find(|r| r.id == target)?.path;
+
+        let fields = match &target {
+            NodeId::Session(idx, i) => {
+                let s = cfg.group(idx)?.sessions.get(*i)?;
+                let addr = Address {
+                    user: s.user.clone(),
+                    host: s.host.clone(),
+                    port: s.port,
+                };
+
+                vec![
+                    EditField::new("Name", "session name", s.name.clone()),
+                    EditField::new(
+                        "Type",
+                        "ssh, telnet or empty to use the one of the group",
+                        connection_type_name(&s.connection_type),
+                    ),
+                    EditField::new("Address", "[username@]host[:port]", addr.to_string()),
+                    EditField::new(
+                        "SSH options",
+                        "-i ~/.ssh/key -o Key=Value -A -X -J group/bastion -L 8080:db:5432",
+                        s.ssh.to_args_string(),
+                    ),
+                    EditField::new("Tags", "prod, db, eu", s.tags.join(", ")),
+                ]
+            }
+            NodeId::Group(idx) => {
+                let sg = cfg.group(idx)?;
+                let defaults = &sg.defaults;
+
+                vec![
+                    EditField::new("Name", "group name", sg.name.clone()),
+                    EditField::new(
+                        "Default type",
+                        "ssh, telnet or empty",
+                        connection_type_name(&defaults.connection_type),
+                    ),
+                    EditField::new(
+                        "Default user",
+                        "user of sessions that don't set one",
+                        defaults.user.clone().unwrap_or_default(),
+                    ),
+                    EditField::new(
+                        "Default port",
+                        "port of sessions that don't set one",
+                        defaults.port.map(|p| p.to_string()).unwrap_or_default(),
+                    ),
+                    EditField::new(
+                        "Default SSH options",
+                        "-i ~/.ssh/key -o Key=Value -J group/bastion",
+                        defaults.ssh.to_args_string(),
+                    ),
+                ]
+            }
+        };
+
+        Some(EditState {
+            target,
+            path,
+            fields,
+            focus: 0,
+            error: None,
+        })
+    }
+
+    pub fn title(&self) -> String {
+        match self.target {
+            NodeId::Session(..) => format!("Edit session {}", self.path),
+            NodeId::Group(_) => format!("Edit group {}", self.path),
+        }
+    }
+
+    /// `group/.../name` path of the edited session or group
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn next_field(&mut self) {
+        self.focus = (self.focus + 1) % self.fields.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+    }
+
+    pub fn focused(&mut self) -> &mut TextArea<'a> {
+        &mut self.fields[self.focus].textarea
+    }
+
+    /// fills in the field for the `name`, `type`, `address`, `ssh-options`,
+    /// `tags`, `user` or `port` setting, false if the target has no such field
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        let i = match (&self.target, key) {
+            (_, "name") => NAME,
+            (_, "type") => TYPE,
+            (NodeId::Session(..), "address") => ADDRESS,
+            (NodeId::Session(..), "ssh-options") => SSH_OPTIONS,
+            (NodeId::Session(..), "tags") => TAGS,
+            (NodeId::Group(_), "user") => GROUP_USER,
+            (NodeId::Group(_), "port") => GROUP_PORT,
+            (NodeId::Group(_), "ssh-options") => GROUP_SSH_OPTIONS,
+            _ => return false,
+        };
+
+        self.fields[i].textarea = TextArea::new(vec![value.to_string()]);
+        true
+    }
+
+    /// validates the form and writes it to `cfg`, nothing is changed on error,
+    /// returns the new path of the session or group
+    pub fn apply(&self, cfg: &mut Config) -> Result<String, String> {
+        let name = parse_name(self.fields[NAME].value())?;
+        let connection_type = parse_connection_type(self.fields[TYPE].value())?;
+
+        let new_path = match self.path.rsplit_once('/') {
+            Some((parent, _)) => join_path(parent, &name),
+            None => name.clone(),
+        };
+        if new_path != self.path && cfg.rows().iter().any(|r| r.path == new_path) {
+            return Err(format!("'{}' already exists", new_path));
+        }
+
+        match &self.target {
+            NodeId::Session(idx, i) => {
+                let addr = Address::parse(self.fields[ADDRESS].value())
+                    .map_err(|e| format!("address: {}", e))?;
+                let ssh = SshOptions::parse(self.fields[SSH_OPTIONS].value())?;
+                let tags = Session::parse_tags(self.fields[TAGS].value());
+
+                let s = cfg
+                    .group_mut(idx)
+                    .and_then(|sg| sg.sessions.get_mut(*i))
+                    .ok_or("session no longer exists")?;
+
+                s.name = name;
+                s.connection_type = connection_type;
+                s.user = addr.user;
+                s.host = addr.host;
+                s.port = addr.port;
+                s.ssh = ssh;
+                s.tags = tags;
+            }
+            NodeId::Group(idx) => {
+                let user = match self.fields[GROUP_USER].value() {
+                    "" => None,
+                    u => Some(u.to_string()),
+                };
+                let port = match self.fields[GROUP_PORT].value() {
+                    "" => None,
+                    p => Some(address::parse_port(p).map_err(|e| e.to_string())?),
+                };
+                let ssh = SshOptions::parse(self.fields[GROUP_SSH_OPTIONS].value())?;
+
+                let sg = cfg.group_mut(idx).ok_or("group no longer exists")?;
+
+                sg.name = name;
+                sg.defaults = SessionDefaults {
+                    user,
+                    port,
+                    connection_type,
+                    ssh,
+                };
+            }
+        }
+
+        cfg.rename_references(&self.path, &new_path);
+        Ok(new_path)
+    }
+}
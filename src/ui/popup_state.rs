@@ -4,6 +4,7 @@ use crate::session_core::{
     connection_type::ConnectionType,
     session::{Session, SessionBuilder},
     session_group::{SessionGroup, SessionGroupBuilder},
+    ssh_options::SshOptions,
 };
 
 #[derive(PartialEq)]
@@ -30,6 +31,7 @@ pub enum PopupBuilderState {
     SessionName,
     SessionConnectionType,
     SessionData,
+    SessionSshArgs,
     SessionAddConfirm,
 
     SessionAddMore,
@@ -46,8 +48,16 @@ pub struct PopupState<'a> {
     sg_state: PopupBuilderState,
 
     pub textarea: tui_textarea::TextArea<'a>,
+    /// validation error of the last submitted input
+    pub error: Option<String>,
 
     pub temp_session_group: Option<SessionGroup>,
+
+    /// `group/.../group` path of the group a single session is added to,
+    /// `None` when a whole new group is created
+    target_group: Option<String>,
+    /// session added to `target_group`, waiting to be put in the config
+    pub temp_session: Option<Session>,
 }
 
 #[allow(clippy::let_and_return)]
@@ -80,7 +90,14 @@ fn sg_prompts() -> &'static HashMap<PopupBuilderState, (&'static str, &'static s
             ),
             (
                 PopupBuilderState::SessionData,
-                ("Connection data:", "username@ip:port"),
+                ("Address:", "[username@]host[:port]"),
+            ),
+            (
+                PopupBuilderState::SessionSshArgs,
+                (
+                    "SSH options (optional):",
+                    "-i ~/.ssh/key -o Key=Value -A -X -J group/bastion -L 8080:db:5432 -D 1080",
+                ),
             ),
             (
                 PopupBuilderState::SessionAddConfirm,
@@ -113,11 +130,27 @@ impl<'a> PopupState<'a> {
 
             sg_state: PopupBuilderState::SessionGroupName,
             textarea: tui_textarea::TextArea::default(),
+            error: None,
 
             temp_session_group: None,
+
+            target_group: None,
+            temp_session: None,
         }
     }
 
+    /// opens the wizard for adding a single session to the group at `group_path`
+    pub fn show_for_group(&mut self, group_path: String) {
+        self.reset_state();
+        self.target_group = Some(group_path);
+        self.sg_state = PopupBuilderState::SessionName;
+        self.show();
+    }
+
+    pub fn target_group(&self) -> Option<&str> {
+        self.target_group.as_deref()
+    }
+
     pub fn get_state(&self) -> PopupBuilderState {
         self.sg_state
     }
@@ -162,21 +195,58 @@ impl<'a> PopupState<'a> {
             }
             PopupBuilderState::SessionData => {
                 if data != PopupStateAction::Next {
-                    self.session_builder
-                        .as_mut()
-                        .unwrap()
-                        .data(data.get_data().clone());
+                    let sb = self.session_builder.as_mut().unwrap();
+
+                    if let Err(e) = sb.address(data.get_data()) {
+                        // stay in this state until the address is valid
+                        self.error = Some(e.to_string());
+                        return;
+                    }
                 }
 
-                self.sg_state = PopupBuilderState::SessionAddConfirm;
+                let is_ssh = self
+                    .session_builder
+                    .as_ref()
+                    .is_some_and(|sb| sb.get_connection_type() != Some(&ConnectionType::Telnet));
+
+                self.sg_state = if is_ssh {
+                    PopupBuilderState::SessionSshArgs
+                } else {
+                    PopupBuilderState::SessionAddConfirm
+                };
             }
-            PopupBuilderState::SessionAddConfirm => {
+            PopupBuilderState::SessionSshArgs => {
                 if data != PopupStateAction::Next {
-                    let sb = self.session_builder.take().unwrap();
-                    self.session_group_builder.add_session(sb);
+                    match SshOptions::parse(data.get_data()) {
+                        Ok(ssh) => {
+                            self.session_builder.as_mut().unwrap().ssh_options(ssh);
+                        }
+                        Err(e) => {
+                            self.error = Some(e);
+                            return;
+                        }
+                    }
                 }
 
-                self.sg_state = PopupBuilderState::SessionAddMore;
+                self.sg_state = PopupBuilderState::SessionAddConfirm;
+            }
+            PopupBuilderState::SessionAddConfirm => {
+                let sb = self.session_builder.take().unwrap();
+
+                if self.target_group.is_some() {
+                    // a single session, there is no group to confirm
+                    if data != PopupStateAction::Next {
+                        self.temp_session = Some(sb.build());
+                    }
+
+                    self.sg_state = PopupBuilderState::Done;
+                } else {
+                    if data != PopupStateAction::Next {
+                        self.session_group_builder.add_session(sb);
+                    }
+
+                    self.sg_state = PopupBuilderState::SessionAddMore;
+                }
             }
             PopupBuilderState::SessionAddMore => {
                 if data == PopupStateAction::Next {
@@ -197,9 +267,15 @@ impl<'a> PopupState<'a> {
             _ => {}
         }
 
+        self.error = None;
         self.clear_textarea();
     }
 
+    /// states in which an empty input is accepted
+    pub fn is_optional(&self) -> bool {
+        self.sg_state == PopupBuilderState::SessionSshArgs
+    }
+
     pub fn is_open(&self) -> bool {
         self.open
     }
@@ -221,8 +297,11 @@ impl<'a> PopupState<'a> {
     pub fn reset_state(&mut self) {
         self.clear_textarea();
         self.hide();
+        self.error = None;
 
         self.session_group_builder = SessionGroup::builder();
+        self.session_builder = None;
+        self.target_group = None;
         self.sg_state = PopupBuilderState::SessionGroupName;
     }
 }
@@ -1,4 +1,5 @@
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -9,11 +10,11 @@ use std::io::{self, stdout, Stdout};
 use std::vec;
 use tui_textarea::{Input, Key};
 
-use crate::{load_cfg_from_file, session_core::session::Session};
+use crate::{error, interop, paths, session_core::session::Session};
 
-use super::view_state::ViewState;
+use super::view_state::{SyncChoice, ViewState};
 use super::{
-    config::Config,
+    config::{Config, NodeId},
     popup_state::{PopupBuilderState, PopupStateAction},
 };
 
@@ -25,7 +26,13 @@ Navigate:
 
 Actions:
   q/ESC: quit
+      /: search
       a: add session group
+      A: add session to group
+      e: edit
+      i: import ~/.ssh/config
+      t: start tunnel
+      T: stop tunnel
       r: remove
       R: reload config";
 
@@ -51,68 +58,64 @@ fn create_centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 fn remove_selected(state: &mut ViewState) {
-    let mut selected_idx = match state.table_state.selected() {
-        Some(i) => i,
-        None => return,
-    };
-
-    for (i, session_group) in state.config.session_groups.iter_mut().enumerate() {
-        if selected_idx == 0 {
-            state.config.session_groups.remove(i);
-            return;
-        }
-
-        selected_idx -= 1;
-
-        for (j, _) in session_group.sessions.iter_mut().enumerate() {
-            if selected_idx == 0 {
-                session_group.sessions.remove(j);
-                return;
-            }
-
-            selected_idx -= 1;
-        }
+    if let Some(row) = state.selected_row() {
+        state.config.remove(&row.id);
+        state.tunnels.remove(&row.path);
     }
 }
 
-fn find_selected(state: &mut ViewState) -> Option<Session> {
-    let mut selected_idx = match state.table_state.selected() {
-        Some(i) => i,
-        None => return None,
-    };
+/// returns the `group/.../session` path and the selected session, with the
+/// defaults of its groups applied
+fn find_selected(state: &mut ViewState) -> Option<(String, Session)> {
+    let row = state.selected_row()?;
+    let session = state.config.effective_session(&row.id)?;
 
-    for session_group in state.config.session_groups.iter() {
-        if selected_idx == 0 {
-            return None;
-        }
+    Some((row.path, session))
+}
 
-        selected_idx -= 1;
+fn start_selected_tunnel(state: &mut ViewState) {
+    let (path, session) = match find_selected(state) {
+        Some(s) => s,
+        None => return,
+    };
 
-        for session in session_group.sessions.iter() {
-            if selected_idx == 0 {
-                return Some(session.clone());
-            }
+    let started = state
+        .config
+        .resolve_jump_chain(&session)
+        .and_then(|jump_chain| state.tunnels.start(&path, &session, &jump_chain));
 
-            selected_idx -= 1;
-        }
+    if let Err(e) = started {
+        state.message = Some(e);
     }
-
-    None
 }
 
-fn handle_normal_mode_events(state: &mut ViewState, cfg_path: &str) -> io::Result<bool> {
+fn handle_normal_mode_events(state: &mut ViewState) -> io::Result<bool> {
     if let Event::Key(key) = event::read()? {
         if key.kind == event::KeyEventKind::Press {
+            state.message = None;
+
             match key.code {
+                KeyCode::Esc if state.filter.is_some() => state.clear_filter(),
                 KeyCode::Char('q') | KeyCode::Esc => {
-                    // Quit
-                    return Ok(true);
+                    // Quit, unless the file has to be merged first
+                    return Ok(state.can_save(true));
+                }
+                KeyCode::Char('/') => state.start_filter(),
+                KeyCode::Char('a' | 'A' | 'e' | 'i' | 'r') if state.read_only.is_some() => {
+                    state.message = state.read_only.clone();
                 }
                 KeyCode::Char('a') => state.popup_state.show(),
-                KeyCode::Char('r') => remove_selected(state),
-                KeyCode::Char('R') => {
-                    state.config = load_cfg_from_file(cfg_path).unwrap_or(Config::new());
+                KeyCode::Char('A') => state.add_session_to_selected(),
+                KeyCode::Char('e') => state.edit_selected(),
+                KeyCode::Char('i') => state.preview_ssh_config_import(),
+                KeyCode::Char('t') => start_selected_tunnel(state),
+                KeyCode::Char('T') => {
+                    if let Some((path, _)) = find_selected(state) {
+                        state.tunnels.stop(&path);
+                    }
                 }
+                KeyCode::Char('r') => remove_selected(state),
+                KeyCode::Char('R') => state.reload(),
                 KeyCode::Enter => state.connected = true,
                 KeyCode::Down | KeyCode::Char('j') => state.next(),
                 KeyCode::Up | KeyCode::Char('k') => state.previous(),
@@ -141,7 +144,7 @@ fn handle_edit_mode_events(state: &mut ViewState) -> io::Result<bool> {
             | PopupBuilderState::SessionAddMore => {}
             _ => {
                 let line = popup_state.textarea.lines()[0].clone();
-                if line.is_empty() {
+                if line.is_empty() && !popup_state.is_optional() {
                     return Ok(false);
                 }
 
@@ -168,6 +171,12 @@ fn handle_edit_mode_events(state: &mut ViewState) -> io::Result<bool> {
                 } else if input.key == Key::Char('n') {
                     popup_state.increment_state(PopupStateAction::Next);
                 }
+
+                // a single session is done after the confirmation
+                if popup_state.get_state() == PopupBuilderState::Done {
+                    state.add_temp_session_to_cfg();
+                    state.popup_state.reset_state();
+                }
             }
             PopupBuilderState::SessionAddMore => {
                 if input.key == Key::Char('y') {
@@ -185,10 +194,116 @@ fn handle_edit_mode_events(state: &mut ViewState) -> io::Result<bool> {
     Ok(false)
 }
 
-fn handle_events(cfg_path: &str, state: &mut ViewState) -> io::Result<bool> {
+fn handle_edit_form_events(state: &mut ViewState) -> io::Result<bool> {
+    let event = event::read()?;
+
+    let edit_state = match state.edit_state.as_mut() {
+        Some(e) => e,
+        None => return Ok(false),
+    };
+
+    if let Event::Key(key) = event {
+        if key.kind != event::KeyEventKind::Press {
+            return Ok(false);
+        }
+
+        match key.code {
+            KeyCode::Esc => state.edit_state = None,
+            KeyCode::Enter => state.save_edit(),
+            KeyCode::Tab | KeyCode::Down => edit_state.next_field(),
+            KeyCode::BackTab | KeyCode::Up => edit_state.previous_field(),
+            _ => {
+                edit_state.focused().input(Input::from(event));
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+fn handle_filter_events(state: &mut ViewState) -> io::Result<bool> {
+    if let Event::Key(key) = event::read()? {
+        if key.kind != event::KeyEventKind::Press {
+            return Ok(false);
+        }
+
+        let filter = match state.filter.as_mut() {
+            Some(f) => f,
+            None => return Ok(false),
+        };
+
+        match key.code {
+            KeyCode::Esc => state.clear_filter(),
+            KeyCode::Enter => {
+                // the filter stays applied, `/` continues the search
+                filter.editing = false;
+                state.connected = true;
+            }
+            KeyCode::Down => state.next(),
+            KeyCode::Up => state.previous(),
+            KeyCode::Backspace => {
+                filter.query.pop();
+                state.select_best_match();
+            }
+            KeyCode::Char(c) => {
+                filter.query.push(c);
+                state.select_best_match();
+            }
+            _ => {}
+        }
+    }
+
+    Ok(false)
+}
+
+fn handle_import_events(state: &mut ViewState) -> io::Result<bool> {
+    if let Event::Key(key) = event::read()? {
+        if key.kind == event::KeyEventKind::Press {
+            if key.code == KeyCode::Char('y') {
+                state.apply_import();
+            }
+
+            // any other key cancels
+            state.import_preview = None;
+        }
+    }
+
+    Ok(false)
+}
+
+fn handle_sync_events(state: &mut ViewState) -> io::Result<bool> {
+    if let Event::Key(key) = event::read()? {
+        if key.kind == event::KeyEventKind::Press {
+            let choice = match key.code {
+                KeyCode::Char('m') => SyncChoice::Merge,
+                KeyCode::Char('y') => SyncChoice::KeepMine,
+                KeyCode::Char('t') => SyncChoice::TakeTheirs,
+                KeyCode::Esc => {
+                    state.sync = None;
+                    return Ok(false);
+                }
+                _ => return Ok(false),
+            };
+
+            return Ok(state.resolve_sync(choice));
+        }
+    }
+
+    Ok(false)
+}
+
+fn handle_events(state: &mut ViewState) -> io::Result<bool> {
     if event::poll(std::time::Duration::from_millis(50))? {
-        if !state.popup_state.is_open() {
-            return handle_normal_mode_events(state, cfg_path);
+        if state.sync.is_some() {
+            return handle_sync_events(state);
+        } else if state.import_preview.is_some() {
+            return handle_import_events(state);
+        } else if state.edit_state.is_some() {
+            return handle_edit_form_events(state);
+        } else if state.filter.as_ref().is_some_and(|f| f.editing) {
+            return handle_filter_events(state);
+        } else if !state.popup_state.is_open() {
+            return handle_normal_mode_events(state);
         } else {
             return handle_edit_mode_events(state);
         }
@@ -198,9 +313,16 @@ fn handle_events(cfg_path: &str, state: &mut ViewState) -> io::Result<bool> {
 }
 
 fn table_ui(state: &mut ViewState, frame: &mut Frame, area: &Rect) {
-    let header_cells = ["Group Name", "Session Name", "Username", "IP", "Port"]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::White)));
+    let header_cells = [
+        "Group Name",
+        "Session Name",
+        "Username",
+        "Host",
+        "Port",
+        "Jump",
+    ]
+    .iter()
+    .map(|h| Cell::from(*h).style(Style::default().fg(Color::White)));
 
     let header = Row::new(header_cells)
         .style(Style::default().bg(Color::DarkGray))
@@ -208,47 +330,151 @@ fn table_ui(state: &mut ViewState, frame: &mut Frame, area: &Rect) {
         .bottom_margin(1);
 
     let mut rows = Vec::new();
+    let visible_rows = state.visible_rows();
 
-    for session_group in state.config.session_groups.iter() {
-        let sg_cells = vec![
-            Cell::from(session_group.name.clone()),
-            Cell::from(" "),
-            Cell::from(" "),
-            Cell::from(" "),
-            Cell::from(" "),
-        ];
-        rows.push(Row::new(sg_cells));
-
-        for session in session_group.sessions.iter() {
-            let s_cells = vec![
+    for row in visible_rows.iter() {
+        if let NodeId::Group(idx) = &row.id {
+            let name = state.config.group(idx).map_or("", |sg| &sg.name);
+            let sg_cells = vec![
+                Cell::from(format!("{}{}", "  ".repeat(row.depth), name)),
+                Cell::from(" "),
+                Cell::from(" "),
+                Cell::from(" "),
+                Cell::from(" "),
                 Cell::from(" "),
-                Cell::from(session.name.clone()),
-                Cell::from(session.get_user_name()),
-                Cell::from(session.get_ip()),
-                Cell::from(session.get_port()),
             ];
-            rows.push(Row::new(s_cells));
+            rows.push(Row::new(sg_cells));
+            continue;
         }
+
+        let session = match state.config.effective_session(&row.id) {
+            Some(s) => s,
+            None => continue,
+        };
+
+        let s_cells = vec![
+            Cell::from(" "),
+            Cell::from(session.name.clone()),
+            Cell::from(session.get_user_name().to_string()),
+            Cell::from(session.host.clone()),
+            Cell::from(session.port().to_string()),
+            Cell::from(
+                session
+                    .ssh
+                    .jump
+                    .iter()
+                    .map(|j| j.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" → "),
+            ),
+        ];
+        rows.push(Row::new(s_cells));
     }
 
+    let title = match &state.filter {
+        Some(_) => {
+            let n = visible_rows
+                .iter()
+                .filter(|r| matches!(r.id, NodeId::Session(..)))
+                .count();
+            format!("Sessions ({} matching)", n)
+        }
+        None => "Sessions".to_string(),
+    };
+
     let t = Table::new(
         rows,
         [
-            Constraint::Percentage(20),
-            Constraint::Percentage(20),
-            Constraint::Percentage(20),
-            Constraint::Percentage(30),
-            Constraint::Percentage(10),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(25),
+            Constraint::Percentage(8),
+            Constraint::Percentage(22),
         ],
     )
     .header(header)
-    .block(Block::default().borders(Borders::ALL).title("Sessions"))
+    .block(Block::default().borders(Borders::ALL).title(title))
     .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
     .highlight_symbol(">> ");
 
     frame.render_stateful_widget(t, *area, &mut state.table_state);
 }
 
+fn details_ui(state: &mut ViewState, frame: &mut Frame, area: &Rect) {
+    let mut lines = Vec::new();
+
+    if let Some((path, session)) = find_selected(state) {
+        lines.push(Line::from(format!(
+            "{} {}",
+            session.connection_type(),
+            session.address()
+        )));
+
+        let ssh = &session.ssh;
+        if let Some(identity_file) = &ssh.identity_file {
+            lines.push(Line::from(format!("Identity: {}", identity_file)));
+        }
+        for o in ssh.options.iter() {
+            lines.push(Line::from(format!("Option: {}", o)));
+        }
+        if ssh.forward_agent {
+            lines.push(Line::from("Agent forwarding"));
+        }
+        if ssh.forward_x11 {
+            lines.push(Line::from("X11 forwarding"));
+        }
+        for j in ssh.jump.iter() {
+            lines.push(Line::from(format!("Jump: {}", j)));
+        }
+        if !ssh.forwards.is_empty() {
+            lines.push(Line::from("Forwards:"));
+            for f in ssh.forwards.iter() {
+                lines.push(Line::from(format!("  {}", f)));
+            }
+        }
+        if !ssh.flags.is_empty() {
+            lines.push(Line::from(format!("Flags: {}", ssh.flags.join(" "))));
+        }
+        if !ssh.extra_args.is_empty() {
+            lines.push(Line::from(format!("Command: {}", ssh.extra_args.join(" "))));
+        }
+        if !session.tags.is_empty() {
+            lines.push(Line::from(format!("Tags: {}", session.tags.join(", "))));
+        }
+        if let Some(t) = state.tunnels.get(&path) {
+            lines.push(Line::from(format!("Tunnel: {}", t.status)));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().title("Session").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, *area);
+}
+
+fn tunnels_ui(state: &ViewState, frame: &mut Frame, area: &Rect) {
+    let lines: Vec<Line> = state
+        .tunnels
+        .tunnels()
+        .iter()
+        .map(|t| {
+            let line = Line::from(format!("{}: {}", t.path, t.status));
+            if t.is_running() {
+                line.green()
+            } else {
+                line.dim()
+            }
+        })
+        .collect();
+
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().title("Tunnels").borders(Borders::ALL));
+
+    frame.render_widget(paragraph, *area);
+}
+
 fn popup_ui(state: &mut ViewState, frame: &mut Frame) {
     let create_block = |title, border, modifier, color| {
         Block::default().borders(border).gray().title(Span::styled(
@@ -264,14 +490,14 @@ fn popup_ui(state: &mut ViewState, frame: &mut Frame) {
     )
     .split(area);
 
+    let title = match state.popup_state.target_group() {
+        Some(group_path) => format!("Add session to {}", group_path),
+        None => "Create new session group".to_string(),
+    };
+
     frame.render_widget(Clear, area); // clears out the background
     frame.render_widget(
-        create_block(
-            "Create new session group",
-            Borders::ALL,
-            Modifier::BOLD,
-            Color::Reset,
-        ),
+        create_block(title, Borders::ALL, Modifier::BOLD, Color::Reset),
         area,
     );
 
@@ -283,11 +509,12 @@ fn popup_ui(state: &mut ViewState, frame: &mut Frame) {
         PopupBuilderState::SessionGroupName
         | PopupBuilderState::SessionName
         | PopupBuilderState::SessionConnectionType
-        | PopupBuilderState::SessionData => {
+        | PopupBuilderState::SessionData
+        | PopupBuilderState::SessionSshArgs => {
             let textarea = &mut state.popup_state.textarea;
 
             textarea.set_block(create_block(
-                prompt,
+                prompt.to_string(),
                 Borders::NONE,
                 Modifier::BOLD,
                 Color::LightBlue,
@@ -316,12 +543,166 @@ fn popup_ui(state: &mut ViewState, frame: &mut Frame) {
             );
         }
     }
+
+    if let Some(err) = &state.popup_state.error {
+        let err_area = Rect {
+            y: area.bottom().saturating_sub(2),
+            height: 1,
+            ..area.inner(&Margin {
+                vertical: 0,
+                horizontal: 2,
+            })
+        };
+
+        frame.render_widget(Paragraph::new(err.as_str()).red(), err_area);
+    }
+}
+
+fn edit_form_ui(state: &mut ViewState, frame: &mut Frame) {
+    let edit_state = match state.edit_state.as_mut() {
+        Some(e) => e,
+        None => return,
+    };
+
+    // three lines per field, the error and the key hints, inside of the border
+    let n = edit_state.fields.len();
+    let height = n as u16 * 3 + 4;
+    let column = create_centered_rect(60, 100, frame.size());
+    let area = Rect {
+        y: column.y + column.height.saturating_sub(height) / 2,
+        height: height.min(column.height),
+        ..column
+    };
+
+    frame.render_widget(Clear, area); // clears out the background
+    frame.render_widget(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(edit_state.title())
+            .bold(),
+        area,
+    );
+
+    let mut constraints = vec![Constraint::Length(3); n];
+    constraints.push(Constraint::Length(1));
+    constraints.push(Constraint::Length(1));
+
+    let chunks = Layout::new(Direction::Vertical, constraints).split(area.inner(&Margin {
+        vertical: 1,
+        horizontal: 2,
+    }));
+
+    let focus = edit_state.focus;
+    for (i, field) in edit_state.fields.iter_mut().enumerate() {
+        let (border_color, cursor_style) = match i == focus {
+            true => (
+                Color::LightBlue,
+                Style::default().add_modifier(Modifier::REVERSED),
+            ),
+            false => (Color::DarkGray, Style::default()),
+        };
+
+        field.textarea.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(border_color))
+                .title(field.label),
+        );
+        field.textarea.set_cursor_style(cursor_style);
+        frame.render_widget(field.textarea.widget(), chunks[i]);
+    }
+
+    if let Some(err) = &edit_state.error {
+        frame.render_widget(Paragraph::new(err.as_str()).red(), chunks[n]);
+    }
+    frame.render_widget(
+        Paragraph::new("Tab/↓: next field  ↑: previous field  Enter: save  Esc: cancel").dim(),
+        chunks[n + 1],
+    );
+}
+
+fn import_popup_ui(state: &ViewState, frame: &mut Frame) {
+    let (title, lines): (&str, Vec<Line>) = match &state.import_preview {
+        Some(Ok(groups)) if groups.is_empty() => (
+            "Import ~/.ssh/config",
+            vec![Line::from("Nothing to import, press any key to close")],
+        ),
+        Some(Ok(groups)) => {
+            let mut lines = vec![
+                Line::from("Import these sessions? (y/n)").bold(),
+                Line::from(""),
+            ];
+            lines.extend(interop::preview_lines(groups).into_iter().map(Line::from));
+            ("Import ~/.ssh/config", lines)
+        }
+        Some(Err(e)) => ("Import failed", vec![Line::from(e.as_str()).red()]),
+        None => return,
+    };
+
+    let area = create_centered_rect(60, 60, frame.size());
+
+    frame.render_widget(Clear, area); // clears out the background
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title).bold())
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn sync_popup_ui(state: &ViewState, frame: &mut Frame) {
+    let prompt = match &state.sync {
+        Some(p) => p,
+        None => return,
+    };
+
+    let merge = match prompt.conflicts.as_slice() {
+        [] => "m: merge both".to_string(),
+        c => format!("m: merge both, keeping your version of {}", c.join(", ")),
+    };
+    let lines = vec![
+        Line::from(format!(
+            "{} was changed by something else since it was loaded.",
+            paths::contract_tilde(&state.config_path)
+        )),
+        Line::from(""),
+        Line::from(merge),
+        Line::from("y: keep yours and overwrite the file"),
+        Line::from("t: take theirs and drop your changes"),
+        Line::from("ESC: cancel"),
+    ];
+
+    // the border and room for wrapped lines
+    let height = lines.len() as u16 + 4;
+    let column = create_centered_rect(60, 100, frame.size());
+    let area = Rect {
+        y: column.y + column.height.saturating_sub(height) / 2,
+        height: height.min(column.height),
+        ..column
+    };
+
+    frame.render_widget(Clear, area); // clears out the background
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Config changed on disk")
+                    .bold(),
+            )
+            .wrap(Wrap { trim: false }),
+        area,
+    );
 }
 
 fn ui(state: &mut ViewState, frame: &mut Frame) {
     let root_layout = Layout::new(
         Direction::Vertical,
-        [Constraint::Length(1), Constraint::Min(0)],
+        [
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ],
     )
     .split(frame.size());
 
@@ -329,7 +710,11 @@ fn ui(state: &mut ViewState, frame: &mut Frame) {
     frame.render_widget(
         Block::new()
             .borders(Borders::TOP)
-            .title(env!("CARGO_CRATE_NAME"))
+            .title(format!(
+                "{} - {}",
+                env!("CARGO_CRATE_NAME"),
+                paths::contract_tilde(&state.config_path)
+            ))
             .title_alignment(Alignment::Center)
             .bold()
             .green(),
@@ -345,26 +730,86 @@ fn ui(state: &mut ViewState, frame: &mut Frame) {
     )
     .split(root_layout[1]);
 
+    // --------
+    // | help |
+    // --------
+    // | info |
+    // --------
+    // | tun  |
+    // --------
+    let side_layout = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(HELP_MENU_TEXT.lines().count() as u16 + 2),
+            Constraint::Min(0),
+            Constraint::Length(match state.tunnels.tunnels().len() {
+                0 => 0,
+                n => n as u16 + 2,
+            }),
+        ],
+    )
+    .split(inner_layout[1]);
+
     // Menu
     let paragraph = Paragraph::new(HELP_MENU_TEXT)
         .block(Block::default().title("Help").dim().borders(Borders::ALL));
 
-    frame.render_widget(paragraph, inner_layout[1]);
+    frame.render_widget(paragraph, side_layout[0]);
+
+    // Selected session
+    details_ui(state, frame, &side_layout[1]);
+
+    // Background tunnels
+    if !state.tunnels.tunnels().is_empty() {
+        tunnels_ui(state, frame, &side_layout[2]);
+    }
 
     // Sessions table
     table_ui(state, frame, &inner_layout[0]);
 
+    // Status bar
+    if let Some(msg) = &state.message {
+        frame.render_widget(Paragraph::new(msg.as_str()).red(), root_layout[2]);
+    } else if let Some(filter) = &state.filter {
+        let mut line = Line::from(vec![
+            Span::from("/").bold(),
+            Span::from(filter.query.as_str()),
+        ]);
+        if filter.editing {
+            line.spans.push(Span::from(" ").reversed());
+        }
+
+        frame.render_widget(Paragraph::new(line), root_layout[2]);
+    } else if state.changed_on_disk() {
+        let text = format!(
+            "{} was changed on disk, R to reload",
+            paths::contract_tilde(&state.config_path)
+        );
+        frame.render_widget(Paragraph::new(text).yellow(), root_layout[2]);
+    } else if let Some(read_only) = &state.read_only {
+        frame.render_widget(Paragraph::new(read_only.as_str()).yellow(), root_layout[2]);
+    }
+
     // Popup (add session group)
     if state.popup_state.is_open() {
         popup_ui(state, frame)
     }
+
+    // Popup (edit session or group)
+    edit_form_ui(state, frame);
+
+    // Popup (import preview)
+    import_popup_ui(state, frame);
+
+    // Popup (config changed on disk)
+    sync_popup_ui(state, frame);
 }
 
 fn connect_selected_ui(
     state: &mut ViewState,
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
 ) -> io::Result<()> {
-    let session = match find_selected(state) {
+    let (_, session) = match find_selected(state) {
         Some(s) => s,
         None => {
             state.connected = false;
@@ -372,7 +817,23 @@ fn connect_selected_ui(
         }
     };
 
-    let text = format!("Connecting to {}", session.data);
+    let jump_chain = match state.config.resolve_jump_chain(&session) {
+        Ok(c) => c,
+        Err(e) => {
+            state.message = Some(e);
+            state.connected = false;
+            return Ok(());
+        }
+    };
+
+    let text = match jump_chain.is_empty() {
+        true => format!("Connecting to {}", session.address()),
+        false => format!(
+            "Connecting to {} via {}",
+            session.address(),
+            jump_chain.join(" -> ")
+        ),
+    };
     execute!(terminal.backend_mut(), DisableMouseCapture)?;
     terminal.draw(|frame| {
         frame.render_widget(
@@ -384,10 +845,14 @@ fn connect_selected_ui(
     terminal.show_cursor()?;
 
     disable_raw_mode()?;
-    session.connect();
+    let connected = session.connect(&jump_chain);
     state.connected = false;
     enable_raw_mode()?;
 
+    if let Err(e) = connected {
+        state.message = Some(e.to_string());
+    }
+
     terminal.hide_cursor()?;
     execute!(terminal.backend_mut(), EnableMouseCapture)?;
     terminal.clear()?;
@@ -395,8 +860,37 @@ fn connect_selected_ui(
     Ok(())
 }
 
-pub fn display(cfg_path: &str, cfg: io::Result<Config>) -> io::Result<()> {
-    let mut state = ViewState::new(cfg.unwrap_or(Config::new()));
+fn run(state: &mut ViewState, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    let mut should_quit = false;
+    while !should_quit {
+        state.tunnels.poll();
+        state.watch();
+
+        if !state.connected {
+            terminal.draw(|frame| ui(state, frame))?;
+            should_quit = handle_events(state)?;
+        } else {
+            connect_selected_ui(state, terminal)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture, Show)
+}
+
+pub fn display(cfg_path: &str, cfg: Config) -> error::Result<()> {
+    let mut state = ViewState::new(cfg, cfg_path.to_string());
+
+    // a panic would otherwise leave the terminal in raw mode
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
 
     enable_raw_mode()?;
     execute!(stdout(), EnterAlternateScreen, EnableMouseCapture)?;
@@ -407,24 +901,17 @@ pub fn display(cfg_path: &str, cfg: io::Result<Config>) -> io::Result<()> {
         .textarea
         .set_cursor_line_style(Style::default());
 
-    let mut should_quit = false;
-    while !should_quit {
-        if !state.connected {
-            terminal.draw(|frame| ui(&mut state, frame))?;
-            should_quit = handle_events(cfg_path, &mut state)?;
-        } else {
-            connect_selected_ui(&mut state, &mut terminal)?;
-        }
-    }
+    let result = run(&mut state, &mut terminal);
+    restore_terminal()?;
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    state.tunnels.stop_all();
 
-    state.config.save(cfg_path);
-    Ok(())
+    // an untouched config isn't written, the file may have been changed by
+    // something else in the meantime. After an error there was no chance to
+    // merge with such a change, so a file changed on disk is left alone
+    let unmerged = result.is_err() && state.changed_on_disk();
+    if state.has_local_changes() && !unmerged {
+        state.config.save(cfg_path)?;
+    }
+    Ok(result?)
 }
@@ -1,20 +1,450 @@
-use super::{config::Config, popup_state::PopupState};
+use super::{
+    config::{Config, NodeId, TreeRow},
+    edit_state::EditState,
+    popup_state::PopupState,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs,
+    time::SystemTime,
+};
+
+use crate::fuzzy;
+use crate::interop::ssh_config;
+use crate::merge;
+use crate::session_core::{session_group::SessionGroup, tunnel::TunnelManager};
 use ratatui::widgets::TableState;
 
+/// The `/` search over the sessions
+pub struct Filter {
+    pub query: String,
+    /// true while the query is being typed
+    pub editing: bool,
+}
+
+/// Sessions matching a filter query, kept until the query or the config
+/// changes so the table isn't scored again on every frame
+struct MatchCache {
+    query: String,
+    config: Config,
+    /// score of each matching session by path
+    scores: HashMap<String, i64>,
+    /// paths of the groups that contain a match
+    groups: HashSet<String>,
+}
+
+impl MatchCache {
+    fn new(config: &Config, query: &str) -> MatchCache {
+        let mut scores = HashMap::new();
+        let mut groups = HashSet::new();
+
+        for (path, session) in config.sessions() {
+            let score = match fuzzy::score_session(query, &path, &session) {
+                Some(score) => score,
+                None => continue,
+            };
+
+            let mut parent = path.as_str();
+            while let Some((group, _)) = parent.rsplit_once('/') {
+                groups.insert(group.to_string());
+                parent = group;
+            }
+            scores.insert(path, score);
+        }
+
+        MatchCache {
+            query: query.to_string(),
+            config: config.clone(),
+            scores,
+            groups,
+        }
+    }
+}
+
+/// Asks how to combine the own changes with a config file that was changed by
+/// something else since it was loaded
+pub struct SyncPrompt {
+    /// the config as it is on disk now
+    pub theirs: Config,
+    modified: Option<SystemTime>,
+    /// what merging would keep the own version of
+    pub conflicts: Vec<String>,
+    /// quit once the config is saved
+    quit: bool,
+}
+
+pub enum SyncChoice {
+    Merge,
+    KeepMine,
+    TakeTheirs,
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 pub struct ViewState<'a> {
     pub table_state: TableState,
     pub config: Config,
+    /// file the config is loaded from and saved to
+    pub config_path: String,
+    /// the config as it was last loaded or saved, what the own changes are
+    /// made to
+    base: Config,
+    /// modification time of the file when `base` was loaded or saved
+    base_modified: Option<SystemTime>,
+    /// modification time of the file when `watch` last looked at it
+    watched_modified: Option<SystemTime>,
+    pub sync: Option<SyncPrompt>,
     pub popup_state: PopupState<'a>,
+    /// form of the session or group being edited
+    pub edit_state: Option<EditState<'a>>,
     pub connected: bool,
+    /// shown in the status bar until the next key press
+    pub message: Option<String>,
+    pub tunnels: TunnelManager,
+    /// sessions waiting for confirmation to be imported, or why the import failed
+    pub import_preview: Option<Result<Vec<SessionGroup>, String>>,
+    pub filter: Option<Filter>,
+    /// why the config can't be saved, nothing can be changed while it is set
+    pub read_only: Option<String>,
+    matches: RefCell<Option<MatchCache>>,
 }
 
 impl<'a> ViewState<'a> {
-    pub fn new(config: Config) -> ViewState<'a> {
-        ViewState {
+    pub fn new(config: Config, config_path: String) -> ViewState<'a> {
+        let mut state = ViewState {
             table_state: TableState::default(),
+            base: config.clone(),
+            base_modified: modified(&config_path),
+            watched_modified: modified(&config_path),
+            sync: None,
             config,
+            config_path,
             popup_state: PopupState::new(),
+            edit_state: None,
             connected: false,
+            message: None,
+            tunnels: TunnelManager::new(),
+            import_preview: None,
+            filter: None,
+            read_only: None,
+            matches: RefCell::new(None),
+        };
+
+        state.check_read_only();
+        state
+    }
+
+    /// looks at the file again to tell if it can be saved, so a config that
+    /// can't be is known before anything is changed
+    fn check_read_only(&mut self) {
+        self.read_only = Config::check_writable(&self.config_path)
+            .err()
+            .map(|e| format!("read-only, {}", e));
+    }
+
+    fn filter_query(&self) -> Option<&str> {
+        match &self.filter {
+            Some(f) if !f.query.trim().is_empty() => Some(&f.query),
+            _ => None,
+        }
+    }
+
+    /// runs `f` on the sessions matching `query`, they are only searched
+    /// again when the query or the config changed
+    fn with_matches<R>(&self, query: &str, f: impl FnOnce(&MatchCache) -> R) -> R {
+        let mut cache = self.matches.borrow_mut();
+
+        if !cache
+            .as_ref()
+            .is_some_and(|c| c.query == query && c.config == self.config)
+        {
+            *cache = None;
+        }
+
+        f(cache.get_or_insert_with(|| MatchCache::new(&self.config, query)))
+    }
+
+    /// the rows shown in the table, without a filter that is every row,
+    /// otherwise the matching sessions and the groups they are in
+    pub fn visible_rows(&self) -> Vec<TreeRow> {
+        let query = match self.filter_query() {
+            Some(q) => q,
+            None => return self.config.rows(),
+        };
+
+        self.with_matches(query, |m| {
+            self.config
+                .rows()
+                .into_iter()
+                .filter(|row| match row.id {
+                    NodeId::Session(..) => m.scores.contains_key(&row.path),
+                    NodeId::Group(_) => m.groups.contains(&row.path),
+                })
+                .collect()
+        })
+    }
+
+    pub fn selected_row(&self) -> Option<TreeRow> {
+        let selected = self.table_state.selected()?;
+        self.visible_rows().into_iter().nth(selected)
+    }
+
+    pub fn start_filter(&mut self) {
+        match self.filter.as_mut() {
+            Some(f) => f.editing = true,
+            None => {
+                self.filter = Some(Filter {
+                    query: String::new(),
+                    editing: true,
+                })
+            }
+        }
+    }
+
+    pub fn clear_filter(&mut self) {
+        let selected = self.selected_row().map(|r| r.path);
+        self.filter = None;
+
+        // keep the highlighted row highlighted in the full table
+        let i = selected.and_then(|path| self.config.rows().iter().position(|r| r.path == path));
+        self.table_state.select(i.or(Some(0)));
+    }
+
+    /// highlights the best matching session after the query changed
+    pub fn select_best_match(&mut self) {
+        let best = self.filter_query().and_then(|query| {
+            self.with_matches(query, |m| {
+                self.config
+                    .rows()
+                    .into_iter()
+                    .filter_map(|row| Some((m.scores.get(&row.path)?, row.path)))
+                    // the first one wins on equal scores
+                    .rev()
+                    .max_by_key(|(score, _)| **score)
+                    .map(|(_, path)| path)
+            })
+        });
+
+        let i = best.and_then(|path| self.visible_rows().iter().position(|r| r.path == path));
+        self.table_state.select(i.or(Some(0)));
+    }
+
+    /// true if the config was changed since it was loaded or saved
+    pub fn has_local_changes(&self) -> bool {
+        self.config.session_groups != self.base.session_groups
+    }
+
+    /// true if the file was written by something else since it was loaded
+    pub fn changed_on_disk(&self) -> bool {
+        modified(&self.config_path) != self.base_modified
+    }
+
+    fn open_sync(&mut self, theirs: Config, modified: Option<SystemTime>, quit: bool) {
+        let conflicts = merge::three_way(&self.base, &self.config, &theirs).conflicts;
+
+        self.sync = Some(SyncPrompt {
+            theirs,
+            modified,
+            conflicts,
+            quit,
+        });
+    }
+
+    /// true if the own changes can be saved without losing what something
+    /// else wrote to the file, otherwise the sync prompt is opened
+    pub fn can_save(&mut self, quit: bool) -> bool {
+        if !self.has_local_changes() || !self.changed_on_disk() {
+            return true;
+        }
+
+        let modified = modified(&self.config_path);
+        match Config::load_or_new(&self.config_path) {
+            Ok(theirs) if theirs.session_groups != self.base.session_groups => {
+                self.open_sync(theirs, modified, quit);
+                false
+            }
+            // only touched, or broken and replaced by the own version, the
+            // broken file is kept as a backup
+            _ => true,
+        }
+    }
+
+    /// loads the file again, the sync prompt is opened if there are own
+    /// changes that would be lost
+    pub fn reload(&mut self) {
+        let modified = modified(&self.config_path);
+
+        match Config::load(&self.config_path) {
+            Ok(theirs) if self.has_local_changes() => self.open_sync(theirs, modified, false),
+            Ok(theirs) => {
+                self.base = theirs.clone();
+                self.base_modified = modified;
+                self.replace_config(theirs);
+                self.check_read_only();
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    /// applies the choice made in the sync prompt, returns true if gcoma
+    /// should quit (and save)
+    pub fn resolve_sync(&mut self, choice: SyncChoice) -> bool {
+        let prompt = match self.sync.take() {
+            Some(p) => p,
+            None => return false,
+        };
+
+        let mut conflicts = Vec::new();
+        match choice {
+            SyncChoice::Merge => {
+                let merged = merge::three_way(&self.base, &self.config, &prompt.theirs);
+                self.replace_config(merged.config);
+                conflicts = merged.conflicts;
+            }
+            SyncChoice::KeepMine => {}
+            SyncChoice::TakeTheirs => self.replace_config(prompt.theirs.clone()),
+        }
+
+        self.base = prompt.theirs;
+        self.base_modified = prompt.modified;
+
+        if !conflicts.is_empty() {
+            // the result is shown before it is saved
+            self.message = Some(format!(
+                "Merged, kept your version of {}",
+                conflicts.join(", ")
+            ));
+            return false;
+        }
+
+        prompt.quit
+    }
+
+    /// reloads the config when the file was changed and nothing would be
+    /// lost, called on every tick of the event loop
+    pub fn watch(&mut self) {
+        let modified = modified(&self.config_path);
+        if modified == self.watched_modified {
+            return;
+        }
+
+        // own changes are only merged on request, and the forms and prompts
+        // point into the config, the file is looked at again once they're gone
+        let busy = self.edit_state.is_some()
+            || self.popup_state.is_open()
+            || self.import_preview.is_some()
+            || self.sync.is_some();
+        if self.has_local_changes() || busy {
+            return;
+        }
+
+        if modified == self.base_modified {
+            self.watched_modified = modified;
+            return;
+        }
+
+        // a file that is still being written may not parse yet, it is read
+        // again until it does, its modification time may not change anymore
+        if let Ok(config) = Config::load(&self.config_path) {
+            self.watched_modified = modified;
+            self.base = config.clone();
+            self.base_modified = modified;
+            self.replace_config(config);
+            self.check_read_only();
+        }
+    }
+
+    /// swaps in another config, the selected group or session stays selected
+    /// if it still exists
+    fn replace_config(&mut self, config: Config) {
+        let selected = self
+            .selected_row()
+            .map(|r| (r.path, matches!(r.id, NodeId::Group(_))));
+        self.config = config;
+
+        let rows = self.visible_rows();
+        let i = selected.and_then(|(path, is_group)| {
+            rows.iter()
+                .position(|r| r.path == path && matches!(r.id, NodeId::Group(_)) == is_group)
+        });
+
+        match (i, self.table_state.selected()) {
+            (Some(i), _) => self.table_state.select(Some(i)),
+            (None, Some(i)) => self
+                .table_state
+                .select(Some(i.min(rows.len().saturating_sub(1)))),
+            (None, None) => {}
+        }
+    }
+
+    pub fn preview_ssh_config_import(&mut self) {
+        let preview = ssh_config::import(&ssh_config::default_path())
+            .map(|groups| self.config.dedup_groups(groups))
+            .map_err(|e| format!("{}: {}", ssh_config::default_path().display(), e));
+
+        self.import_preview = Some(preview);
+    }
+
+    pub fn apply_import(&mut self) {
+        if let Some(Ok(groups)) = self.import_preview.take() {
+            self.config.merge_groups(groups);
+        }
+    }
+
+    /// opens the edit form for the selected row
+    pub fn edit_selected(&mut self) {
+        if let Some(row) = self.selected_row() {
+            self.edit_state = EditState::new(&self.config, row.id);
+        }
+    }
+
+    /// writes the edit form to the config and closes it, on a validation
+    /// error the form stays open
+    pub fn save_edit(&mut self) {
+        let edit_state = match self.edit_state.as_mut() {
+            Some(e) => e,
+            None => return,
+        };
+
+        match edit_state.apply(&mut self.config) {
+            Ok(new_path) => {
+                self.tunnels.rename(edit_state.path(), &new_path);
+                self.edit_state = None;
+            }
+            Err(e) => edit_state.error = Some(e),
+        }
+    }
+
+    /// opens the wizard for adding a session to the selected group, or to the
+    /// group of the selected session
+    pub fn add_session_to_selected(&mut self) {
+        let path = match self.selected_row() {
+            Some(row) => match row.id {
+                NodeId::Group(_) => row.path,
+                NodeId::Session(..) => match row.path.rsplit_once('/') {
+                    Some((group_path, _)) => group_path.to_string(),
+                    None => return,
+                },
+            },
+            None => return,
+        };
+
+        self.popup_state.show_for_group(path);
+    }
+
+    pub fn add_temp_session_to_cfg(&mut self) {
+        let session = match self.popup_state.temp_session.take() {
+            Some(s) => s,
+            None => return,
+        };
+
+        if let Some(group_path) = self.popup_state.target_group() {
+            if let Err(e) = self.config.add_session(group_path, session) {
+                self.message = Some(e);
+            }
         }
     }
 
@@ -25,42 +455,20 @@ impl<'a> ViewState<'a> {
     }
 
     pub fn next(&mut self) {
+        let rows = self.visible_rows().len();
         let i = match self.table_state.selected() {
-            Some(i) => {
-                if i >= (self.config.session_groups.len() - 1)
-                    + (self
-                        .config
-                        .session_groups
-                        .iter()
-                        .map(|s| s.sessions.len())
-                        .sum::<usize>())
-                {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+            Some(i) if i + 1 < rows => i + 1,
+            _ => 0,
         };
 
         self.table_state.select(Some(i));
     }
 
     pub fn previous(&mut self) {
+        let rows = self.visible_rows().len();
         let i = match self.table_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    (self.config.session_groups.len() - 1)
-                        + (self
-                            .config
-                            .session_groups
-                            .iter()
-                            .map(|s| s.sessions.len())
-                            .sum::<usize>())
-                } else {
-                    i - 1
-                }
-            }
+            Some(0) => rows.saturating_sub(1),
+            Some(i) => i - 1,
             None => 0,
         };
 